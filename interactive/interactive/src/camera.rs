@@ -0,0 +1,57 @@
+use interactive::physics::{self, Real, Vector2};
+
+const MIN_PIXELS_PER_METRE: Real = 10.0;
+const MAX_PIXELS_PER_METRE: Real = 5000.0;
+
+/// Maps world space (metres, `y` down) to window pixels and back.
+///
+/// The physics never sees screen coordinates; rendering goes through
+/// `world_to_screen` and input through `screen_to_world`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    /// World point shown at the centre of the viewport.
+    pub centre: Vector2,
+    pub pixels_per_metre: Real,
+    /// Viewport size in pixels.
+    pub viewport: Vector2,
+}
+
+impl Camera {
+    /// A camera showing the box from the origin to `physics::DEFAULT_BOUNDS`
+    /// at 100 pixels per metre.
+    pub fn new(viewport: Vector2) -> Self {
+        Self {
+            centre: physics::DEFAULT_BOUNDS / 2.0,
+            pixels_per_metre: 100.0,
+            viewport,
+        }
+    }
+
+    pub fn world_to_screen(&self, point: Vector2) -> Vector2 {
+        (point - self.centre) * self.pixels_per_metre + self.viewport / 2.0
+    }
+
+    pub fn screen_to_world(&self, point: Vector2) -> Vector2 {
+        (point - self.viewport / 2.0) / self.pixels_per_metre + self.centre
+    }
+
+    /// Moves the view by `delta` screen pixels, so the world follows the
+    /// pointer when dragged.
+    pub fn pan(&mut self, delta: Vector2) {
+        self.centre -= delta / self.pixels_per_metre;
+    }
+
+    /// Scales the zoom by `factor`, keeping the world point under the
+    /// screen point `anchor` in place.
+    pub fn zoom_at(&mut self, anchor: Vector2, factor: Real) {
+        let anchored = self.screen_to_world(anchor);
+        self.pixels_per_metre =
+            (self.pixels_per_metre * factor).clamp(MIN_PIXELS_PER_METRE, MAX_PIXELS_PER_METRE);
+        self.centre += anchored - self.screen_to_world(anchor);
+    }
+
+    /// Length in pixels of `length` metres.
+    pub fn scale(&self, length: Real) -> Real {
+        length * self.pixels_per_metre
+    }
+}
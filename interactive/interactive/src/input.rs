@@ -0,0 +1,40 @@
+use interactive::physics::{InputState, PointerButton};
+use raylib::prelude::*;
+
+use crate::camera::Camera;
+
+/// Highest GLFW key code raylib knows about.
+const MAX_KEY_CODE: i32 = 348;
+
+/// Reads raylib's input state into a snapshot the solver can consume, with
+/// pointer and touch positions unprojected into world space by `camera`.
+pub fn poll(rl: &RaylibHandle, camera: &Camera) -> InputState {
+    let mut input = InputState::new();
+
+    input.pointer_position = camera.screen_to_world(rl.get_mouse_position().into());
+    for (button, raylib_button) in [
+        (PointerButton::Left, MouseButton::MOUSE_BUTTON_LEFT),
+        (PointerButton::Right, MouseButton::MOUSE_BUTTON_RIGHT),
+        (PointerButton::Middle, MouseButton::MOUSE_BUTTON_MIDDLE),
+    ] {
+        if rl.is_mouse_button_down(raylib_button) {
+            input.buttons_down.push(button);
+        }
+    }
+
+    for code in 0..=MAX_KEY_CODE {
+        if let Some(key) = key_from_i32(code) {
+            if rl.is_key_down(key) {
+                input.keys_down.push(code as u32);
+            }
+        }
+    }
+
+    for index in 0..rl.get_touch_point_count() {
+        input
+            .touch_points
+            .push(camera.screen_to_world(rl.get_touch_position(index).into()));
+    }
+
+    input
+}
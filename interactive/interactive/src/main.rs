@@ -1,225 +1,25 @@
-use crate::constraints::*;
+use std::any::Any;
 
-use crate::objects::*;
 use ffi::Rectangle;
-use ndarray::Array1;
-use ndarray::Array2;
-
-use ndarray::ArrayView1;
-use ndarray::Axis;
-use ndarray::Ix1;
-use ndarray::Ix2;
-use ndarray_linalg::solve::Inverse;
+use interactive::physics::{
+    self, BodyContacts, BroadphaseKind, Circle, Constraint, DistanceConstraint, FixedTimestep,
+    ForceGenerator, IntegratorKind, LinearDrag, LinearSolverKind, PhysicsObject, Polygon, Real,
+    ScreenEdge, Solver, StepReport, UniformGravity,
+};
 use raylib::prelude::*;
 
-pub mod constraints;
-pub mod objects;
-
-struct Solver {
-    scene_objects: Vec<Box<dyn PhysicsObject>>,
-    constraints: Vec<Box<dyn Constraint>>,
-
-    previous_force: Array1<f32>,
-}
-
-impl Solver {
-    fn new() -> Self {
-        Self {
-            scene_objects: Vec::with_capacity(100),
-            constraints: Vec::with_capacity(100),
-            previous_force: Array1::<f32>::zeros(Ix1(0)),
-        }
-    }
-
-    fn apply_gravity(&mut self) {
-        for ele in self.scene_objects.iter_mut() {
-            ele.accelerate(Vector2::new(0_f32, 981_f32) * 64_f32);
-        }
-    }
-
-    fn apply_air_resistance(&mut self) {
-        for ele in self.scene_objects.iter_mut() {
-            let mut velocity = ele.get_velocity();
-            velocity *= 0.001_f32;
-            ele.get_old_position_mut().x += velocity.x;
-            ele.get_old_position_mut().y += velocity.y;
-        }
-    }
-
-    fn solve_constraints(&mut self, d: &RaylibDrawHandle) {
-        let mut J = Array2::<f32>::zeros(Ix2(self.constraints.len(), self.scene_objects.len() * 2));
-        for constraint_index in 0..self.constraints.len() {
-            let mut constraint = self.constraints.get_mut(constraint_index).unwrap();
-            let constraint_jacobian = constraint.jacobian(&mut self.scene_objects, d);
-            // let mut J_slice = J.slice_axis_mut(
-            //     Axis(1),
-            //     ndarray::Slice::new(
-            //         constraint_index.try_into().unwrap(),
-            //         Some(constraint_index.try_into().unwrap()),
-            //         1,
-            //     ),
-            // );
-            for J_index in 0..(self.scene_objects.len() * 2) {
-                J[(constraint_index, J_index)] = constraint_jacobian[J_index];
-            }
-        }
-        let J_clone = J.clone();
-        let mut JT = J_clone.t();
+use crate::camera::Camera;
 
-        let mut M = Array2::<f32>::zeros(Ix2(
-            2 * self.scene_objects.len(),
-            2 * self.scene_objects.len(),
-        ) as ndarray::Dim<[usize; 2]>);
-        for scene_index in 0..self.scene_objects.len() {
-            M[(scene_index * 2, scene_index * 2)] = self.scene_objects[scene_index].get_mass();
-            M[(scene_index * 2 + 1, scene_index * 2 + 1)] =
-                self.scene_objects[scene_index].get_mass();
-        }
-        let mut W = Inverse::inv(&M).expect("Invalid mass matrix. Couldn't be invverted.");
-
-        let mut Q = Array1::<f32>::from_shape_fn((self.scene_objects.len() * 2), |i| {
-            let axis_selector = i % 2;
-            match axis_selector {
-                0_usize => {
-                    self.scene_objects[i / 2].get_acceleration().x
-                        * self.scene_objects[i / 2].get_mass()
-                }
-                1_usize => {
-                    self.scene_objects[i / 2].get_acceleration().y
-                        * self.scene_objects[i / 2].get_mass()
-                }
-                _ => 0_f32,
-            }
-        });
-
-        if self.previous_force.len() != self.scene_objects.len() * 2 {
-            self.previous_force.append(
-                Axis(0),
-                Array1::<f32>::zeros(Ix1(self.scene_objects.len() * 2)).view(),
-            );
-        }
-
-        let mut right: ndarray::ArrayBase<ndarray::OwnedRepr<f32>, ndarray::Dim<[usize; 2]>> =
-            J.clone() * W.clone();
-        right = right * Q;
-        right = right * -1_f32;
-
-        let mut left: ndarray::ArrayBase<ndarray::OwnedRepr<f32>, ndarray::Dim<[usize; 2]>> =
-            J * W * JT;
-
-        let norm = |vector: &Array1<f32>| {
-            let mut sum: f32 = 0_f32;
-            for ele in vector.iter() {
-                sum += ele.powi(2);
-            }
-            sum.sqrt()
-        };
-
-        let conjugate_gradient =
-            |left: &Array2<f32>, right: ArrayView1<f32>, previous_force: &Array1<f32>| {
-                // Reimplementation of the Wikipedia conugate gradient code from https://github.com/ange-yaghi/simple-2d-constraint-solver/blob/master/src/conjugate_gradient_sle_solver.cpp
-                // Initialize necessary values at k = 0
-                let mut residual: Array1<f32> = &right - left.dot(previous_force);
-                let mut search_direction = residual.clone();
-                let mut old_resid_norm = norm(&residual);
-                let mut x = previous_force.clone();
-                let mut iteration_count = 0;
-
-                while iteration_count < 1000 && old_resid_norm > f32::EPSILON {
-                    let left_search_direction = left.dot(&search_direction);
-                    let step_size: f32 =
-                        old_resid_norm.powi(2) / (search_direction.dot(&left_search_direction));
-                    x = x.clone() + step_size * search_direction.clone();
-                    // println!("test: {}", step_size * left_search_direction);
-                    // break;
-                    residual = residual - step_size * left_search_direction;
-                    let new_resid_norm = norm(&residual);
-                    search_direction = residual.clone()
-                        + (new_resid_norm / old_resid_norm).powi(2) * search_direction.clone();
-                    old_resid_norm = new_resid_norm;
-                    iteration_count += 1;
-                }
-                x
-            };
-
-        let mut constraint_forces: &mut Array2<f32> = &mut Default::default();
-        right.clone_into(constraint_forces);
-        constraint_forces
-            .columns_mut()
-            .into_iter()
-            .for_each(|mut constraint_right| {
-                let constraint_force =
-                    conjugate_gradient(&left, constraint_right.view(), &self.previous_force);
-                for index in 0..constraint_force.len() {
-                    constraint_right[index] = constraint_force[index];
-                }
-            });
-
-        let constraint_forces = constraint_forces.sum_axis(Axis(0));
-
-        // Apply calculated forces
-        for index in 0..constraint_forces.len() / 2 {
-            let mut prev_acceleration = self.scene_objects[index].get_acceleration();
-            self.scene_objects[index].accelerate(Vector2 {
-                x: constraint_forces[index * 2],
-                y: constraint_forces[index * 2 + 1],
-            });
-            let mut velocity_coefficient =
-                self.scene_objects[index].get_acceleration() / prev_acceleration;
-
-            if prev_acceleration.x == 0_f32 {
-                velocity_coefficient.x = if self.scene_objects[index].get_acceleration().x == 0_f32
-                {
-                    1_f32
-                } else {
-                    0_f32
-                }
-            }
-            if prev_acceleration.y == 0_f32 {
-                velocity_coefficient.y = if self.scene_objects[index].get_acceleration().y == 0_f32
-                {
-                    1_f32
-                } else {
-                    0_f32
-                }
-            }
-
-            let mut object_velocity = self.scene_objects[index].get_velocity();
-            object_velocity *= velocity_coefficient;
-            *self.scene_objects[index].get_old_position_mut() =
-                self.scene_objects[index].get_position() - object_velocity;
-        }
-    }
-
-    fn apply_constraints(&mut self, d: &RaylibDrawHandle) {
-        if d.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT) {
-            let centre_position = d.get_mouse_position() * 64_f32; //Vector2::new(640_f32 / 2_f32, 480_f32 / 2_f32) * 64_f32;
-            let centre_radius = 100_f32 * 64_f32;
-
-            for obj in self.scene_objects.iter_mut() {
-                let to_obj: Vector2 = obj.get_position() - centre_position;
-                let dist: f32 = (to_obj.x.powf(2f32) + to_obj.y.powf(2f32)).sqrt();
-
-                if dist > centre_radius {
-                    let original_position = obj.get_position();
-                    let n: Vector2 = to_obj / dist;
-                    obj.set_position(centre_position + n * centre_radius);
-                    let position_offset = obj.get_position() - original_position;
-                    obj.set_old_position(obj.get_old_position() + position_offset / 2_f32);
-                }
-            }
-        }
-    }
-
-    fn update_positions(&mut self, dt: f32) {
-        for ele in self.scene_objects.iter_mut() {
-            ele.update(dt);
-        }
-    }
-}
+mod camera;
+mod input;
+mod render;
 
 fn main() {
-    let (mut rl, thread) = raylib::init().size(640, 480).title("Hello, World").build();
+    let (mut rl, thread) = raylib::init()
+        .size(640, 480)
+        .resizable()
+        .title("Hello, World")
+        .build();
     rl.set_target_fps(60);
 
     let mut solver: Solver = Solver::new();
@@ -228,22 +28,44 @@ fn main() {
         let element = Box::from(Circle::new());
         solver.scene_objects.push(element);
         let element = &mut solver.scene_objects[i];
-        (*element).set_position(Vector2::new(
-            (*element).get_position().x + ((rand::random::<f32>() * 300_f32) - 150_f32) * 64_f32,
-            (*element).get_position().y + ((rand::random::<f32>() * 80_f32) - 40_f32) * 64_f32,
+        (*element).set_position(physics::Vector2::new(
+            (*element).get_position().x + ((rand::random::<Real>() * 3.0) - 1.5),
+            (*element).get_position().y + ((rand::random::<Real>() * 0.8) - 0.4),
         ));
-        (*element).set_old_position((*element).get_position());
     }
+    // A few polygons among the circles: a box that tumbles, one that keeps
+    // upright, and a pentagon.
+    let polygons = [
+        (Polygon::rectangle(0.5, 0.3), 1.6),
+        (Polygon::axis_aligned_box(0.4, 0.4), 3.2),
+        (Polygon::regular(5, 0.25), 4.8),
+    ];
+    for (mut polygon, x) in polygons {
+        polygon.set_position(physics::Vector2::new(x, 1.0));
+        solver.scene_objects.push(Box::new(polygon));
+    }
+
+    let mut air_resistance = true;
+    solver.force_generators = scene_forces(air_resistance);
+    let mut broadphase_kind = BroadphaseKind::SpatialHash;
+    solver.constraints = scene_constraints(broadphase_kind);
+    let mut show_broadphase = false;
 
-    solver.constraints.push(Box::new(ScreenEdge::new()));
-    //solver.constraints.push(Box::new(MouseFollow::new()));
+    let mut linear_solver_kind = LinearSolverKind::GaussSeidel;
+    solver.linear_solver = linear_solver_kind.build();
+    let mut integrator_kind = IntegratorKind::Verlet;
+    solver.integrator = integrator_kind.build();
 
-    let mut air_resistance: bool = true;
+    let mut timestep = FixedTimestep::new(1.0 / 60.0, 10);
+    let mut report = StepReport::default();
+    let mut camera = Camera::new(physics::Vector2::new(640.0, 480.0));
+    // Body the camera stays centred on, if any.
+    let mut followed: Option<usize> = None;
 
     while !rl.window_should_close() {
         let mut d = rl.begin_drawing(&thread);
 
-        //ele.update((1_f32 / 144_f32) / 1.7_f32);
+        let air_resistance_before = air_resistance;
         d.gui_toggle(
             Rectangle {
                 x: 10_f32,
@@ -258,28 +80,187 @@ fn main() {
             if d.is_key_pressed(KeyboardKey::KEY_W) {
                 air_resistance = !air_resistance;
             }
+            if air_resistance != air_resistance_before {
+                solver.force_generators = scene_forces(air_resistance);
+            }
+            if d.is_key_pressed(KeyboardKey::KEY_C) {
+                solver.warm_starting = !solver.warm_starting;
+            }
+            if d.is_key_pressed(KeyboardKey::KEY_S) {
+                linear_solver_kind = linear_solver_kind.next();
+                solver.linear_solver = linear_solver_kind.build();
+            }
+            if d.is_key_pressed(KeyboardKey::KEY_I) {
+                integrator_kind = integrator_kind.next();
+                solver.integrator = integrator_kind.build();
+            }
+            if d.is_key_pressed(KeyboardKey::KEY_B) {
+                broadphase_kind = broadphase_kind.next();
+                solver.constraints = scene_constraints(broadphase_kind);
+            }
+            if d.is_key_pressed(KeyboardKey::KEY_G) {
+                show_broadphase = !show_broadphase;
+            }
         }
 
-        const SUB_STEPS: u32 = 10;
-        let dt = 0.0167f32;
-        let sub_dt: f32 = dt / SUB_STEPS as f32;
-        for _i in 0..SUB_STEPS {
-            solver.apply_gravity();
-
-            solver.apply_constraints(&d);
-            solver.solve_constraints(&d);
-
-            if air_resistance {
-                solver.apply_air_resistance();
+        camera.viewport =
+            physics::Vector2::new(d.get_screen_width() as Real, d.get_screen_height() as Real);
+        {
+            let mouse_position: physics::Vector2 = d.get_mouse_position().into();
+            let wheel = d.get_mouse_wheel_move();
+            if wheel != 0_f32 {
+                camera.zoom_at(mouse_position, Real::powf(1.1, wheel as Real));
+            }
+            if d.is_mouse_button_down(MouseButton::MOUSE_BUTTON_RIGHT) {
+                camera.pan(d.get_mouse_delta().into());
+                followed = None;
             }
+            if d.is_key_pressed(KeyboardKey::KEY_F) {
+                followed = match followed {
+                    Some(_) => None,
+                    None => nearest_body(&solver, camera.screen_to_world(mouse_position)),
+                };
+            }
+        }
+        let input = input::poll(&d, &camera);
 
-            solver.update_positions(sub_dt);
+        if let Some(step_report) = timestep.advance(&mut solver, d.get_frame_time() as Real, &input)
+        {
+            report = step_report;
+        }
+        if let Some(index) = followed {
+            camera.centre = timestep.interpolated_position(&solver, index);
         }
 
         d.clear_background(Color::WHITE);
 
-        for ele in solver.scene_objects.iter_mut() {
-            ele.draw(&mut d);
+        let positions: Vec<physics::Vector2> = (0..solver.scene_objects.len())
+            .map(|index| timestep.interpolated_position(&solver, index))
+            .collect();
+        // The debug view shows what the solver's own broadphase found in the
+        // last substep, rather than running another one.
+        let contacts = body_contacts(&solver);
+        if let (true, Some(contacts)) = (show_broadphase, contacts) {
+            render::draw_broadphase(
+                &mut d,
+                &camera,
+                &contacts.broadphase.debug_cells(),
+                contacts.candidate_pairs(),
+                &positions,
+            );
+        }
+        for (body_a, body_b) in [ROD, ROPE] {
+            render::draw_link(&mut d, &camera, positions[body_a], positions[body_b]);
+        }
+        for (index, (ele, &position)) in solver.scene_objects.iter().zip(&positions).enumerate() {
+            let angle = timestep.interpolated_angle(&solver, index);
+            render::draw_object(&mut d, &camera, ele.as_ref(), position, angle);
+        }
+
+        d.draw_text(
+            &format!("Press S to change solver: {}", solver.linear_solver.name()),
+            10,
+            40,
+            20,
+            Color::DARKGRAY,
+        );
+        d.draw_text(
+            &format!(
+                "Press C for warm starting: {}",
+                if solver.warm_starting { "on" } else { "off" }
+            ),
+            10,
+            64,
+            20,
+            Color::DARKGRAY,
+        );
+        d.draw_text(
+            &format!("Press I to change integrator: {}", solver.integrator.name()),
+            10,
+            88,
+            20,
+            Color::DARKGRAY,
+        );
+        d.draw_text(
+            &match followed {
+                Some(index) => format!("Press F to stop following body {}", index),
+                None => "Press F to follow the body under the pointer".to_string(),
+            },
+            10,
+            112,
+            20,
+            Color::DARKGRAY,
+        );
+        if let Some(contacts) = contacts {
+            d.draw_text(
+                &format!(
+                    "Press B to change broadphase: {} (G to {} it)",
+                    contacts.broadphase.name(),
+                    if show_broadphase { "hide" } else { "show" }
+                ),
+                10,
+                136,
+                20,
+                Color::DARKGRAY,
+            );
+        }
+        render::draw_step_report(&mut d, &report, 10, 160);
+        if let (true, Some(contacts)) = (show_broadphase, contacts) {
+            d.draw_text(
+                &format!(
+                    "{} candidate pairs, {} contacts",
+                    contacts.candidate_pairs().len(),
+                    contacts.contacts().len()
+                ),
+                10,
+                226,
+                14,
+                Color::DARKGRAY,
+            );
         }
     }
 }
+
+/// Gravity, plus linear drag when `air_resistance` is on.
+fn scene_forces(air_resistance: bool) -> Vec<Box<dyn ForceGenerator>> {
+    let mut forces: Vec<Box<dyn ForceGenerator>> = vec![Box::new(UniformGravity::default())];
+    if air_resistance {
+        forces.push(Box::new(LinearDrag::new(0.6)));
+    }
+    forces
+}
+
+/// Circles joined by a rigid rod, and by a rope that can go slack.
+const ROD: (usize, usize) = (0, 1);
+const ROPE: (usize, usize) = (2, 3);
+
+/// The screen edges, contacts between bodies with contact pairs found by a
+/// broadphase of `broadphase_kind`, and the rod and rope.
+fn scene_constraints(broadphase_kind: BroadphaseKind) -> Vec<Box<dyn Constraint>> {
+    vec![
+        Box::new(ScreenEdge::new()),
+        Box::new(BodyContacts::with_broadphase(broadphase_kind.build())),
+        Box::new(DistanceConstraint::new(ROD.0, ROD.1, 0.4)),
+        Box::new(DistanceConstraint::rope(ROPE.0, ROPE.1, 0.8)),
+        //Box::new(MouseFollow::new()),
+    ]
+}
+
+/// The solver's contact constraint, for the broadphase debug view.
+fn body_contacts(solver: &Solver) -> Option<&BodyContacts> {
+    solver
+        .constraints
+        .iter()
+        .find_map(|constraint| (constraint.as_ref() as &dyn Any).downcast_ref::<BodyContacts>())
+}
+
+/// Index of the body closest to the world-space `point`.
+fn nearest_body(solver: &Solver, point: physics::Vector2) -> Option<usize> {
+    solver
+        .scene_objects
+        .iter()
+        .enumerate()
+        .map(|(index, ele)| (index, (ele.get_position() - point).length_sqr()))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(index, _)| index)
+}
@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+
+use crate::physics::math::{Real, Vector2};
+use crate::physics::objects::{PhysicsObject, Shape};
+
+/// Axis-aligned bounding box, in world space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vector2,
+    pub max: Vector2,
+}
+
+impl Aabb {
+    pub fn new(min: Vector2, max: Vector2) -> Self {
+        Self { min, max }
+    }
+
+    /// Smallest box around `object` in its current pose.
+    pub fn of(object: &dyn PhysicsObject) -> Self {
+        let position = object.get_position();
+        match object.shape() {
+            Shape::Circle { radius } => {
+                let extent = Vector2::new(radius, radius);
+                Self::new(position - extent, position + extent)
+            }
+            Shape::Polygon { vertices } => {
+                let mut bounds = Self::new(position, position);
+                for &vertex in vertices {
+                    let point = position + object.to_world(vertex);
+                    bounds.min = Vector2::new(bounds.min.x.min(point.x), bounds.min.y.min(point.y));
+                    bounds.max = Vector2::new(bounds.max.x.max(point.x), bounds.max.y.max(point.y));
+                }
+                bounds
+            }
+        }
+    }
+
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+    }
+}
+
+/// Culls the pairs of bodies that cannot be touching, so the contact
+/// generator only runs its exact test on the candidates left.
+///
+/// Pairs are `(a, b)` with `a < b`, sorted and without repeats, and include
+/// every pair whose bounding boxes overlap.
+pub trait Broadphase {
+    fn name(&self) -> &'static str;
+    fn pairs(&mut self, scene_objects: &[Box<dyn PhysicsObject>]) -> Vec<(usize, usize)>;
+    /// Cells the last call to `pairs` used, for drawing. Methods without a
+    /// grid have none.
+    fn debug_cells(&self) -> Vec<Aabb> {
+        Vec::new()
+    }
+}
+
+kinds! {
+    /// The broadphases available in the demos.
+    BroadphaseKind builds Broadphase {
+        AllPairs => AllPairs,
+        SpatialHash => SpatialHash::default(),
+        SweepAndPrune => SweepAndPrune,
+    }
+}
+
+/// Every pair with overlapping boxes, found by testing all `N²/2` of them.
+/// Only worth it for small scenes, or as a reference for the others.
+pub struct AllPairs;
+
+impl Broadphase for AllPairs {
+    fn name(&self) -> &'static str {
+        "All pairs"
+    }
+
+    fn pairs(&mut self, scene_objects: &[Box<dyn PhysicsObject>]) -> Vec<(usize, usize)> {
+        let bounds: Vec<Aabb> = scene_objects
+            .iter()
+            .map(|ele| Aabb::of(ele.as_ref()))
+            .collect();
+        let mut pairs = Vec::new();
+        for body_a in 0..bounds.len() {
+            for body_b in body_a + 1..bounds.len() {
+                if bounds[body_a].overlaps(&bounds[body_b]) {
+                    pairs.push((body_a, body_b));
+                }
+            }
+        }
+        pairs
+    }
+}
+
+/// Uniform grid stored sparsely in a hash map. Each body is filed under every
+/// cell its box touches, and only bodies sharing a cell are tested.
+///
+/// Works best with `cell_size` a little larger than a typical body. Much
+/// smaller cells file each body many times; much larger ones put most of the
+/// scene in a few cells.
+pub struct SpatialHash {
+    /// Side length of a cell, in metres.
+    pub cell_size: Real,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: Real) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    fn cell(&self, point: Vector2) -> (i64, i64) {
+        (
+            (point.x / self.cell_size).floor() as i64,
+            (point.y / self.cell_size).floor() as i64,
+        )
+    }
+}
+
+impl Default for SpatialHash {
+    fn default() -> Self {
+        Self::new(0.2)
+    }
+}
+
+impl Broadphase for SpatialHash {
+    fn name(&self) -> &'static str {
+        "Spatial hash"
+    }
+
+    fn pairs(&mut self, scene_objects: &[Box<dyn PhysicsObject>]) -> Vec<(usize, usize)> {
+        let bounds: Vec<Aabb> = scene_objects
+            .iter()
+            .map(|ele| Aabb::of(ele.as_ref()))
+            .collect();
+
+        self.cells.clear();
+        for (index, bound) in bounds.iter().enumerate() {
+            let (min_x, min_y) = self.cell(bound.min);
+            let (max_x, max_y) = self.cell(bound.max);
+            for x in min_x..=max_x {
+                for y in min_y..=max_y {
+                    self.cells.entry((x, y)).or_default().push(index);
+                }
+            }
+        }
+
+        // Bodies sharing several cells are found once per cell, hence the
+        // dedup.
+        let mut pairs = Vec::new();
+        for members in self.cells.values() {
+            for (offset, &body_a) in members.iter().enumerate() {
+                for &body_b in &members[offset + 1..] {
+                    if bounds[body_a].overlaps(&bounds[body_b]) {
+                        pairs.push((body_a, body_b));
+                    }
+                }
+            }
+        }
+        pairs.sort_unstable();
+        pairs.dedup();
+        pairs
+    }
+
+    fn debug_cells(&self) -> Vec<Aabb> {
+        self.cells
+            .keys()
+            .map(|&(x, y)| {
+                let min = Vector2::new(x as Real, y as Real) * self.cell_size;
+                Aabb::new(min, min + Vector2::new(self.cell_size, self.cell_size))
+            })
+            .collect()
+    }
+}
+
+/// Sorts the boxes along x and sweeps across them, keeping the boxes whose x
+/// extent is still open. Only those overlap in x, so only they are tested
+/// in y.
+pub struct SweepAndPrune;
+
+impl Broadphase for SweepAndPrune {
+    fn name(&self) -> &'static str {
+        "Sweep and prune"
+    }
+
+    fn pairs(&mut self, scene_objects: &[Box<dyn PhysicsObject>]) -> Vec<(usize, usize)> {
+        let bounds: Vec<Aabb> = scene_objects
+            .iter()
+            .map(|ele| Aabb::of(ele.as_ref()))
+            .collect();
+        let mut order: Vec<usize> = (0..bounds.len()).collect();
+        order.sort_unstable_by(|&a, &b| bounds[a].min.x.total_cmp(&bounds[b].min.x));
+
+        let mut pairs = Vec::new();
+        let mut open: Vec<usize> = Vec::new();
+        for &index in &order {
+            let bound = bounds[index];
+            open.retain(|&other| bounds[other].max.x >= bound.min.x);
+            for &other in &open {
+                if bound.min.y <= bounds[other].max.y && bounds[other].min.y <= bound.max.y {
+                    pairs.push((index.min(other), index.max(other)));
+                }
+            }
+            open.push(index);
+        }
+        pairs.sort_unstable();
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+    use crate::physics::objects::{Circle, Polygon};
+
+    fn sorted_pairs(
+        broadphase: &mut dyn Broadphase,
+        scene: &[Box<dyn PhysicsObject>],
+    ) -> Vec<(usize, usize)> {
+        let mut pairs: Vec<_> = broadphase
+            .pairs(scene)
+            .into_iter()
+            .map(|(a, b)| (a.min(b), a.max(b)))
+            .collect();
+        pairs.sort_unstable();
+        pairs
+    }
+
+    #[test]
+    fn broadphases_agree_on_pairs() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut scene: Vec<Box<dyn PhysicsObject>> = Vec::new();
+        for index in 0..200 {
+            let mut object: Box<dyn PhysicsObject> = if index % 5 == 0 {
+                Box::new(Polygon::rectangle(
+                    rng.gen_range(0.05..0.4),
+                    rng.gen_range(0.05..0.4),
+                ))
+            } else {
+                Box::new(Circle {
+                    radius: rng.gen_range(0.02..0.15),
+                    ..Circle::new()
+                })
+            };
+            object.set_position(Vector2::new(
+                rng.gen_range(0.0..6.4),
+                rng.gen_range(0.0..4.8),
+            ));
+            object.set_angle(rng.gen_range(0.0..3.0));
+            scene.push(object);
+        }
+
+        let expected = sorted_pairs(&mut AllPairs, &scene);
+        assert!(!expected.is_empty());
+        for kind in BroadphaseKind::ALL {
+            let mut broadphase = kind.build();
+            assert_eq!(
+                sorted_pairs(broadphase.as_mut(), &scene),
+                expected,
+                "{}",
+                broadphase.name()
+            );
+        }
+    }
+}
@@ -0,0 +1,943 @@
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+
+use crate::physics::input::{InputState, PointerButton};
+use crate::physics::math::{Real, Vector2};
+use crate::physics::objects::{PhysicsObject, Shape};
+
+/// Baumgarte stabilization gains for a constraint's rows.
+///
+/// The solver adds `bias = β/h² C + damping/h Ċ` to the right-hand side,
+/// where `h` is the substep length, so drift in `C` is pulled back towards
+/// zero instead of accumulating. Both gains are dimensionless: `β` is
+/// roughly the fraction of the position error corrected per substep and
+/// `damping` the fraction of `Ċ` removed. Rows with a positive
+/// `ConstraintRow::target` use `(Ċ - Ċ_target)/h` instead.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Baumgarte {
+    pub beta: Real,
+    pub damping: Real,
+}
+
+impl Baumgarte {
+    pub fn new(beta: Real, damping: Real) -> Self {
+        Self { beta, damping }
+    }
+}
+
+impl Default for Baumgarte {
+    fn default() -> Self {
+        Self::new(0.2, 1.0)
+    }
+}
+
+/// Restitution for the normal rows of a contact constraint. Each bounce is
+/// decided from the closing speed at the start of the substep and handed to
+/// the solver as `ConstraintRow::target`.
+#[derive(Debug, Clone, Default)]
+pub struct Bounces {
+    /// `q̇` at the start of the current substep, laid out like the columns
+    /// of `J`.
+    velocities: Vec<Real>,
+    /// Bounces that began this substep, and those that began the last one,
+    /// by normal row.
+    started: HashMap<RowId, Bounce>,
+    carried: HashMap<RowId, Bounce>,
+}
+
+#[derive(Debug, Clone)]
+struct Bounce {
+    /// `Ċ` the row should separate at.
+    separating: Real,
+    /// The row's Jacobian when the bounce began, for the stages at which
+    /// its contact has already parted.
+    entries: Vec<(usize, Real)>,
+}
+
+impl Bounces {
+    /// Records the bodies' velocities for the substep about to start. Bounces
+    /// that began in the last substep are carried into this one: a contact
+    /// that first appears at a later stage of a multi-stage integrator only
+    /// gets part of its bounce in the substep it began.
+    pub fn begin_substep(&mut self, scene_objects: &[Box<dyn PhysicsObject>]) {
+        self.velocities.clear();
+        for ele in scene_objects {
+            let velocity = ele.get_velocity();
+            self.velocities
+                .extend([velocity.x, velocity.y, ele.get_angular_velocity()]);
+        }
+        self.carried = std::mem::take(&mut self.started);
+        // Rows of bodies removed since cannot be evaluated.
+        let dof = self.velocities.len();
+        self.carried
+            .retain(|_, bounce| bounce.entries.iter().all(|&(column, _)| column < dof));
+    }
+
+    /// Sets the target of the normal `row`, with coefficient of restitution
+    /// `restitution`. The row bounces if it was closing faster than
+    /// `threshold` at the start of the substep, and should then separate at
+    /// `-e` times that. Slower contacts do not bounce, as resting contacts
+    /// close a little every substep under gravity and bouncing those would
+    /// make them jitter.
+    ///
+    /// The solver drives `Ċ` to the target from whatever trial state it
+    /// evaluates, so the target is moved on by however much `Ċ` has already
+    /// changed this substep. Every stage of a multi-stage integrator then
+    /// asks for the same change, and the bounce is not averaged away.
+    pub fn apply(
+        &mut self,
+        scene_objects: &[Box<dyn PhysicsObject>],
+        row: &mut ConstraintRow,
+        restitution: Real,
+        threshold: Real,
+    ) {
+        let pre_step_velocity = self.pre_step_velocity(scene_objects, &row.entries);
+        let separating = match self.carried.get(&row.id) {
+            Some(bounce) if pre_step_velocity >= bounce.separating - threshold => return,
+            Some(bounce) => bounce.separating,
+            None if pre_step_velocity < -threshold => {
+                self.started
+                    .entry(row.id)
+                    .or_insert_with(|| Bounce {
+                        separating: -restitution * pre_step_velocity,
+                        entries: row.entries.clone(),
+                    })
+                    .separating
+            }
+            None => return,
+        };
+        row.target = separating + row_velocity(scene_objects, &row.entries) - pre_step_velocity;
+    }
+
+    /// Adds a row for each bounce still under way whose contact is missing
+    /// from `rows`. Bodies can part at a later stage of the substep in which
+    /// they bounce, and without these rows those stages would leave the
+    /// bounce out.
+    pub fn add_parted(
+        &self,
+        scene_objects: &[Box<dyn PhysicsObject>],
+        rows: &mut Vec<ConstraintRow>,
+        threshold: Real,
+    ) {
+        if self.started.is_empty() && self.carried.is_empty() {
+            return;
+        }
+        let bouncing: HashSet<RowId> = rows
+            .iter()
+            .filter(|row| row.target > 0.0)
+            .map(|row| row.id)
+            .collect();
+        for (&id, bounce) in self.started.iter().chain(self.carried.iter()) {
+            if bouncing.contains(&id) {
+                continue;
+            }
+            let pre_step_velocity = self.pre_step_velocity(scene_objects, &bounce.entries);
+            if pre_step_velocity >= bounce.separating - threshold {
+                continue;
+            }
+            rows.push(ConstraintRow {
+                bounds: (0.0, Real::INFINITY),
+                target: bounce.separating + row_velocity(scene_objects, &bounce.entries)
+                    - pre_step_velocity,
+                ..ConstraintRow::new(id, bounce.entries.clone(), 0.0)
+            });
+        }
+    }
+
+    /// `Ċ` of the row `entries` at the start of the substep. Bodies added
+    /// since use their current velocity.
+    fn pre_step_velocity(
+        &self,
+        scene_objects: &[Box<dyn PhysicsObject>],
+        entries: &[(usize, Real)],
+    ) -> Real {
+        entries
+            .iter()
+            .map(|&(column, value)| {
+                let velocity = match self.velocities.get(column) {
+                    Some(&velocity) => velocity,
+                    None => column_velocity(scene_objects, column),
+                };
+                value * velocity
+            })
+            .sum()
+    }
+}
+
+/// Component `column` of `q̇`.
+fn column_velocity(scene_objects: &[Box<dyn PhysicsObject>], column: usize) -> Real {
+    let body = &scene_objects[column / DOF_PER_BODY];
+    match column % DOF_PER_BODY {
+        0 => body.get_velocity().x,
+        1 => body.get_velocity().y,
+        _ => body.get_angular_velocity(),
+    }
+}
+
+/// `Ċ = J q̇` of the row `entries`.
+fn row_velocity(scene_objects: &[Box<dyn PhysicsObject>], entries: &[(usize, Real)]) -> Real {
+    entries
+        .iter()
+        .map(|&(column, value)| value * column_velocity(scene_objects, column))
+        .sum()
+}
+
+/// Stands in for the second body of a row that only involves one.
+pub const NO_BODY: usize = usize::MAX;
+
+/// Identifies a row within its constraint from one substep to the next,
+/// so the solver can carry its λ over for warm starting even as rows come
+/// and go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RowId {
+    pub bodies: (usize, usize),
+    /// Distinguishes rows on the same bodies, e.g. the axis of an edge.
+    pub feature: u32,
+}
+
+impl RowId {
+    pub fn new(body_a: usize, body_b: usize, feature: u32) -> Self {
+        Self {
+            bodies: (body_a, body_b),
+            feature,
+        }
+    }
+}
+
+/// Coordinates per body in `q`: `x`, `y` and the angle `θ`.
+pub const DOF_PER_BODY: usize = 3;
+
+/// One scalar row `C_i` of the system `[J W Jᵀ]λ = -J̇q̇ - J W Q - bias`,
+/// with everything the solver needs to assemble it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConstraintRow {
+    /// Non-zero entries of the row of `J`, as `(column, value)` pairs. Rows
+    /// are `DOF_PER_BODY * scene_objects.len()` wide, laid out as
+    /// `[x0, y0, θ0, x1, y1, θ1, ...]`.
+    pub entries: Vec<(usize, Real)>,
+    /// Value of `C`.
+    pub c: Real,
+    /// Lower and upper bound on λ. Equalities are unbounded; an inequality
+    /// `C ≥ 0` can only push, so its λ is bounded below by 0.
+    pub bounds: (Real, Real),
+    /// For rows whose bounds scale with another row's λ, such as friction
+    /// held to `μ λₙ`, the index of that row among the constraint's rows.
+    /// `bounds` are then multiples of it, e.g. `(-μ, μ)`, and the row should
+    /// come after it.
+    pub scale_row: Option<usize>,
+    /// `J̇q̇`. Zero for rows whose Jacobian is constant, such as a circle
+    /// against the screen edges. Nonlinear constraints (distances,
+    /// pendulums) must provide it or they drift every step.
+    pub j_dot_q_dot: Real,
+    /// `Ċ` the row should have at the end of the substep, for rows that
+    /// should bounce. Rows with zero are stabilized with the constraint's
+    /// `baumgarte` as usual.
+    pub target: Real,
+    /// Stable identity of the row, used to key the warm start cache.
+    pub id: RowId,
+}
+
+impl ConstraintRow {
+    /// An unbounded equality row with no `J̇q̇` or bounce target.
+    pub fn new(id: RowId, entries: Vec<(usize, Real)>, c: Real) -> Self {
+        Self {
+            entries,
+            c,
+            bounds: (Real::NEG_INFINITY, Real::INFINITY),
+            scale_row: None,
+            j_dot_q_dot: 0.0,
+            target: 0.0,
+            id,
+        }
+    }
+}
+
+/// A constraint contributes a block of scalar rows to the λ system, each
+/// one physical constraint `C_i`.
+///
+/// Constraints are `Any`, so a frontend can find a particular one among
+/// `Solver::constraints` and read its state, e.g. for a debug view.
+pub trait Constraint: Any {
+    /// This constraint's rows for the current state. The solver calls this
+    /// once per acceleration evaluation.
+    fn rows(
+        &mut self,
+        scene_objects: &[Box<dyn PhysicsObject>],
+        input: &InputState,
+    ) -> Vec<ConstraintRow>;
+    /// Stabilization gains applied to every row of this constraint.
+    fn baumgarte(&self) -> Baumgarte {
+        Baumgarte::default()
+    }
+    /// Called once at the start of each substep, before any evaluation, with
+    /// the bodies as they were at the end of the last one.
+    fn begin_substep(&mut self, _scene_objects: &[Box<dyn PhysicsObject>]) {}
+}
+
+/// One violated bound: a point of object `index` is past `bound` on `axis`
+/// (0 = x, 1 = y). `normal` points back into the box along that axis.
+///
+/// Circles are held by the point of their rim facing the edge, polygons by
+/// each of their vertices. `offset` is the vertex relative to the centre in
+/// world space, zero for a circle, and `vertex` numbers it within the body.
+/// `radius` is how far the held point lies past `offset` towards the edge,
+/// zero for a polygon.
+struct EdgeRow {
+    index: usize,
+    vertex: u32,
+    offset: Vector2,
+    radius: Real,
+    axis: usize,
+    bound: Real,
+    normal: Real,
+}
+
+impl EdgeRow {
+    /// `n̂`, the unit vector back into the box.
+    fn direction(&self) -> Vector2 {
+        if self.axis == 0 {
+            Vector2::new(self.normal, 0.0)
+        } else {
+            Vector2::new(0.0, self.normal)
+        }
+    }
+
+    /// The held point relative to the body's centre, which is also where
+    /// friction acts.
+    fn contact_offset(&self) -> Vector2 {
+        self.offset - self.direction() * self.radius
+    }
+
+    /// The row keeping the held point `p + w - r n̂` inside, with
+    /// `J = [n̂ᵀ, w × n̂]`; the rim term drops out, as `n̂ × n̂ = 0`.
+    /// `J̇q̇ = -ω² w·n̂` is the centripetal pull on the held vertex, zero for
+    /// circles, whose held point slides round the rim rather than turning
+    /// with the body.
+    fn normal_row(&self, scene_objects: &[Box<dyn PhysicsObject>]) -> ConstraintRow {
+        let body = &scene_objects[self.index];
+        let point = body.get_position() + self.contact_offset();
+        let coordinate = if self.axis == 0 { point.x } else { point.y };
+        let angular_velocity = body.get_angular_velocity();
+        let column = self.index * DOF_PER_BODY;
+        ConstraintRow {
+            // Edges are one-sided: they may push a body back in but never
+            // pull it towards the edge.
+            bounds: (0.0, Real::INFINITY),
+            j_dot_q_dot: -angular_velocity * angular_velocity * self.offset.dot(self.direction()),
+            ..ConstraintRow::new(
+                self.row_id(),
+                vec![
+                    (column + self.axis, self.normal),
+                    (column + 2, self.offset.cross(self.direction())),
+                ],
+                (coordinate - self.bound) * self.normal,
+            )
+        }
+    }
+
+    fn row_id(&self) -> RowId {
+        let side = if self.normal > 0.0 { 0 } else { 1 };
+        RowId::new(
+            self.index,
+            NO_BODY,
+            self.vertex * 8 + self.axis as u32 * 2 + side,
+        )
+    }
+
+    /// Unit tangent of the edge, along the other axis.
+    fn tangent(&self) -> Vector2 {
+        if self.axis == 0 {
+            Vector2::new(0.0, 1.0)
+        } else {
+            Vector2::new(1.0, 0.0)
+        }
+    }
+
+    /// The row for the velocity of the contact point along the tangent,
+    /// `J = [tᵀ, r × t]`, limited to `(-μ, μ)` times the λ of the normal
+    /// row at `normal_row`, with `μ` from the body's slip along the edge.
+    fn friction_row(
+        &self,
+        scene_objects: &[Box<dyn PhysicsObject>],
+        normal_row: usize,
+    ) -> ConstraintRow {
+        let body = &scene_objects[self.index];
+        let offset = self.contact_offset();
+        let slip =
+            (body.get_velocity() + offset.perp() * body.get_angular_velocity()).dot(self.tangent());
+        let coefficient = body.get_friction().coefficient(slip);
+        let column = self.index * DOF_PER_BODY;
+        ConstraintRow {
+            bounds: (-coefficient, coefficient),
+            scale_row: Some(normal_row),
+            ..ConstraintRow::new(
+                self.friction_row_id(),
+                vec![
+                    (column + 1 - self.axis, 1.0),
+                    (column + 2, offset.cross(self.tangent())),
+                ],
+                0.0,
+            )
+        }
+    }
+
+    fn friction_row_id(&self) -> RowId {
+        let RowId { bodies, feature } = self.row_id();
+        RowId::new(bodies.0, bodies.1, feature + 4)
+    }
+}
+
+/// World-space size of the box `ScreenEdge` and `MouseFollow` start with,
+/// in metres. It matches the original 640×480 window at 100 pixels per metre.
+pub const DEFAULT_BOUNDS: Vector2 = Vector2 { x: 6.4, y: 4.8 };
+
+/// Finds the points outside the box from `min` to `max`, one row per
+/// offending axis. Bodies the box cannot push (zero inverse mass) are left
+/// out, as their rows would be empty.
+fn edge_rows(scene_objects: &[Box<dyn PhysicsObject>], min: Vector2, max: Vector2) -> Vec<EdgeRow> {
+    let mut rows = Vec::new();
+    let mut push_point = |index: usize, vertex: usize, point: Vector2, offset: Vector2, radius| {
+        for (axis, coordinate, lower, upper) in
+            [(0, point.x, min.x, max.x), (1, point.y, min.y, max.y)]
+        {
+            let (bound, normal) = if coordinate - radius < lower {
+                (lower, 1.0)
+            } else if coordinate + radius > upper {
+                (upper, -1.0)
+            } else {
+                continue;
+            };
+            rows.push(EdgeRow {
+                index,
+                vertex: vertex as u32,
+                offset,
+                radius,
+                axis,
+                bound,
+                normal,
+            });
+        }
+    };
+    for (index, obj) in scene_objects.iter().enumerate() {
+        if obj.get_inverse_mass() == 0.0 {
+            continue;
+        }
+        let position = obj.get_position();
+        match obj.shape() {
+            Shape::Circle { radius } => push_point(index, 0, position, Vector2::zero(), radius),
+            Shape::Polygon { vertices } => {
+                for (vertex, &local) in vertices.iter().enumerate() {
+                    let offset = obj.to_world(local);
+                    push_point(index, vertex, position + offset, offset, 0.0);
+                }
+            }
+        }
+    }
+    rows
+}
+
+/// Keeps every body inside the axis-aligned box from `min` to `max`, in
+/// world units. Each edge contact also gets a friction row along the edge,
+/// bounded by the body's own coefficients times the normal row's λ, and
+/// bounces with the body's own restitution.
+pub struct ScreenEdge {
+    pub min: Vector2,
+    pub max: Vector2,
+    pub baumgarte: Baumgarte,
+    /// Slowest closing speed, in metres per second, that bounces. See
+    /// `Bounces::apply`.
+    pub restitution_threshold: Real,
+    bounces: Bounces,
+}
+impl ScreenEdge {
+    pub fn new() -> Self {
+        Self {
+            min: Vector2::zero(),
+            max: DEFAULT_BOUNDS,
+            baumgarte: Baumgarte::default(),
+            restitution_threshold: 0.5,
+            bounces: Bounces::default(),
+        }
+    }
+
+    fn active_rows(
+        &self,
+        scene_objects: &[Box<dyn PhysicsObject>],
+        input: &InputState,
+    ) -> Vec<EdgeRow> {
+        if input.drag_position(PointerButton::Left).is_none() {
+            edge_rows(scene_objects, self.min, self.max)
+        } else {
+            Vec::new()
+        }
+    }
+}
+impl Default for ScreenEdge {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Constraint for ScreenEdge {
+    fn rows(
+        &mut self,
+        scene_objects: &[Box<dyn PhysicsObject>],
+        input: &InputState,
+    ) -> Vec<ConstraintRow> {
+        let mut rows = Vec::new();
+        for edge in self.active_rows(scene_objects, input) {
+            let normal_row = rows.len();
+            let mut row = edge.normal_row(scene_objects);
+            let restitution = scene_objects[edge.index].get_restitution();
+            self.bounces.apply(
+                scene_objects,
+                &mut row,
+                restitution,
+                self.restitution_threshold,
+            );
+            rows.push(row);
+            rows.push(edge.friction_row(scene_objects, normal_row));
+        }
+        self.bounces
+            .add_parted(scene_objects, &mut rows, self.restitution_threshold);
+        rows
+    }
+
+    fn baumgarte(&self) -> Baumgarte {
+        self.baumgarte
+    }
+
+    fn begin_substep(&mut self, scene_objects: &[Box<dyn PhysicsObject>]) {
+        self.bounces.begin_substep(scene_objects);
+    }
+}
+
+pub struct MouseFollow {
+    pub min: Vector2,
+    pub max: Vector2,
+    pub baumgarte: Baumgarte,
+}
+impl MouseFollow {
+    pub fn new() -> Self {
+        Self {
+            min: Vector2::zero(),
+            max: DEFAULT_BOUNDS,
+            baumgarte: Baumgarte::default(),
+        }
+    }
+
+    fn active_rows(
+        &self,
+        scene_objects: &[Box<dyn PhysicsObject>],
+        input: &InputState,
+    ) -> Vec<EdgeRow> {
+        if input.drag_position(PointerButton::Left).is_none() {
+            edge_rows(scene_objects, self.min, self.max)
+                .into_iter()
+                .filter(|row| row.axis == 1 && row.normal > 0.0)
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+}
+impl Default for MouseFollow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Constraint for MouseFollow {
+    fn rows(
+        &mut self,
+        scene_objects: &[Box<dyn PhysicsObject>],
+        input: &InputState,
+    ) -> Vec<ConstraintRow> {
+        self.active_rows(scene_objects, input)
+            .iter()
+            .map(|edge| edge.normal_row(scene_objects))
+            .collect()
+    }
+
+    fn baumgarte(&self) -> Baumgarte {
+        self.baumgarte
+    }
+}
+
+/// Keeps a point on object `index` at `length` from a fixed `anchor`, like a
+/// rigid pendulum rod. The point is `local_offset` from the centre in body
+/// space, at `w = R(θ) r` in world space.
+///
+/// Uses `C = ½(|d|² - l²)` with `d = p + w - a`, so `J = [dᵀ, w × d]` and
+/// `J̇q̇ = |ḋ|² - ω² d·w`, where `ḋ = v + ω w⊥`.
+pub struct Pendulum {
+    pub index: usize,
+    pub anchor: Vector2,
+    pub length: Real,
+    /// Where the rod attaches, relative to the centre in body space.
+    pub local_offset: Vector2,
+    pub baumgarte: Baumgarte,
+}
+impl Pendulum {
+    pub fn new(index: usize, anchor: Vector2, length: Real) -> Self {
+        Self {
+            index,
+            anchor,
+            length,
+            local_offset: Vector2::zero(),
+            baumgarte: Baumgarte::default(),
+        }
+    }
+
+    /// World-space offset `w` of the attachment point and the rod vector `d`.
+    fn geometry(&self, scene_objects: &[Box<dyn PhysicsObject>]) -> (Vector2, Vector2) {
+        let body = &scene_objects[self.index];
+        let offset = body.to_world(self.local_offset);
+        (offset, body.get_position() + offset - self.anchor)
+    }
+}
+impl Constraint for Pendulum {
+    fn rows(
+        &mut self,
+        scene_objects: &[Box<dyn PhysicsObject>],
+        _input: &InputState,
+    ) -> Vec<ConstraintRow> {
+        let (offset, rod) = self.geometry(scene_objects);
+        let body = &scene_objects[self.index];
+        let angular_velocity = body.get_angular_velocity();
+        let rod_velocity = body.get_velocity() + offset.perp() * angular_velocity;
+        let column = self.index * DOF_PER_BODY;
+        vec![ConstraintRow {
+            j_dot_q_dot: rod_velocity.length_sqr()
+                - angular_velocity * angular_velocity * rod.dot(offset),
+            ..ConstraintRow::new(
+                RowId::new(self.index, NO_BODY, 0),
+                vec![
+                    (column, rod.x),
+                    (column + 1, rod.y),
+                    (column + 2, offset.cross(rod)),
+                ],
+                0.5 * (rod.length_sqr() - self.length * self.length),
+            )
+        }]
+    }
+
+    fn baumgarte(&self) -> Baumgarte {
+        self.baumgarte
+    }
+}
+
+/// Keeps a point on `body_a` and a point on `body_b` apart, like a rigid rod
+/// or, with limits, a rope. The points are the anchors in each body's space,
+/// at `w_a` and `w_b` in world space.
+///
+/// Uses `C = ½(|d|² - l²)` with `d = p_b + w_b - p_a - w_a`, so
+/// `J = [-dᵀ, -(w_a × d), dᵀ, w_b × d]` and
+/// `J̇q̇ = |ḋ|² + ω_a² d·w_a - ω_b² d·w_b`. With both anchors at the centres
+/// this is the paper's `J = [-(p_b - p_a)ᵀ, (p_b - p_a)ᵀ]` with
+/// `J̇q̇ = |v_b - v_a|²`.
+pub struct DistanceConstraint {
+    pub body_a: usize,
+    pub body_b: usize,
+    /// Attachment points, relative to each body's centre in body space.
+    pub local_anchor_a: Vector2,
+    pub local_anchor_b: Vector2,
+    /// Length of the rod. Only used while both limits are `None`.
+    pub length: Real,
+    /// With either limit set the distance is free between them, and only a
+    /// limit that is passed adds a row. A rope has just `max_length`.
+    pub min_length: Option<Real>,
+    pub max_length: Option<Real>,
+    pub baumgarte: Baumgarte,
+}
+
+/// The row a `DistanceConstraint` adds this substep: the length it holds,
+/// the bounds on λ, and which limit it is for its `RowId`.
+struct DistanceRow {
+    length: Real,
+    bounds: (Real, Real),
+    feature: u32,
+}
+
+impl DistanceConstraint {
+    /// A rigid rod of `length` between the centres of the two bodies.
+    pub fn new(body_a: usize, body_b: usize, length: Real) -> Self {
+        Self {
+            body_a,
+            body_b,
+            local_anchor_a: Vector2::zero(),
+            local_anchor_b: Vector2::zero(),
+            length,
+            min_length: None,
+            max_length: None,
+            baumgarte: Baumgarte::default(),
+        }
+    }
+
+    /// A rope between the centres, slack until it reaches `max_length`.
+    pub fn rope(body_a: usize, body_b: usize, max_length: Real) -> Self {
+        Self {
+            max_length: Some(max_length),
+            ..Self::new(body_a, body_b, max_length)
+        }
+    }
+
+    /// World-space anchor offsets `w_a`, `w_b` and the span `d` between them.
+    fn geometry(&self, scene_objects: &[Box<dyn PhysicsObject>]) -> (Vector2, Vector2, Vector2) {
+        let (a, b) = (&scene_objects[self.body_a], &scene_objects[self.body_b]);
+        let offset_a = a.to_world(self.local_anchor_a);
+        let offset_b = b.to_world(self.local_anchor_b);
+        let span = b.get_position() + offset_b - a.get_position() - offset_a;
+        (offset_a, offset_b, span)
+    }
+
+    /// A rod is an equality. Past `min_length`, `C ≥ 0` may only push the
+    /// bodies apart; past `max_length`, `C ≤ 0` may only pull them together.
+    fn active_row(&self, scene_objects: &[Box<dyn PhysicsObject>]) -> Option<DistanceRow> {
+        if self.min_length.is_none() && self.max_length.is_none() {
+            return Some(DistanceRow {
+                length: self.length,
+                bounds: (Real::NEG_INFINITY, Real::INFINITY),
+                feature: 0,
+            });
+        }
+        let (_, _, span) = self.geometry(scene_objects);
+        let distance = span.length();
+        match (self.min_length, self.max_length) {
+            (Some(min), _) if distance < min => Some(DistanceRow {
+                length: min,
+                bounds: (0.0, Real::INFINITY),
+                feature: 1,
+            }),
+            (_, Some(max)) if distance > max => Some(DistanceRow {
+                length: max,
+                bounds: (Real::NEG_INFINITY, 0.0),
+                feature: 2,
+            }),
+            _ => None,
+        }
+    }
+}
+impl Constraint for DistanceConstraint {
+    fn rows(
+        &mut self,
+        scene_objects: &[Box<dyn PhysicsObject>],
+        _input: &InputState,
+    ) -> Vec<ConstraintRow> {
+        let Some(row) = self.active_row(scene_objects) else {
+            return Vec::new();
+        };
+        let (offset_a, offset_b, span) = self.geometry(scene_objects);
+        let (a, b) = (&scene_objects[self.body_a], &scene_objects[self.body_b]);
+        let angular_velocity_a = a.get_angular_velocity();
+        let angular_velocity_b = b.get_angular_velocity();
+        let span_velocity = b.get_velocity() + offset_b.perp() * angular_velocity_b
+            - a.get_velocity()
+            - offset_a.perp() * angular_velocity_a;
+        let column_a = self.body_a * DOF_PER_BODY;
+        let column_b = self.body_b * DOF_PER_BODY;
+        vec![ConstraintRow {
+            bounds: row.bounds,
+            j_dot_q_dot: span_velocity.length_sqr()
+                + angular_velocity_a * angular_velocity_a * span.dot(offset_a)
+                - angular_velocity_b * angular_velocity_b * span.dot(offset_b),
+            ..ConstraintRow::new(
+                RowId::new(self.body_a, self.body_b, row.feature),
+                vec![
+                    (column_a, -span.x),
+                    (column_a + 1, -span.y),
+                    (column_a + 2, -offset_a.cross(span)),
+                    (column_b, span.x),
+                    (column_b + 1, span.y),
+                    (column_b + 2, offset_b.cross(span)),
+                ],
+                0.5 * (span.length_sqr() - row.length * row.length),
+            )
+        }]
+    }
+
+    fn baumgarte(&self) -> Baumgarte {
+        self.baumgarte
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::forces::UniformGravity;
+    use crate::physics::integrators::IntegratorKind;
+    use crate::physics::objects::{BodyType, Circle, Polygon};
+    use crate::physics::solver::Solver;
+
+    const STEP: Real = 1.0 / 60.0;
+    const SUB_STEPS: u32 = 10;
+
+    /// A solver with gravity only, so nothing else takes energy out.
+    fn solver_with(bodies: Vec<Box<dyn PhysicsObject>>) -> Solver {
+        let mut solver = Solver::new();
+        solver.force_generators = vec![Box::new(UniformGravity::default())];
+        solver.scene_objects = bodies;
+        solver
+    }
+
+    #[test]
+    fn circle_comes_to_rest_on_the_floor() {
+        let circle = Circle::new();
+        let radius = circle.radius;
+        let mut solver = solver_with(vec![Box::new(circle)]);
+        solver.constraints.push(Box::new(ScreenEdge::new()));
+
+        let input = InputState::new();
+        for _ in 0..300 {
+            solver.step(STEP, SUB_STEPS, &input);
+        }
+
+        let body = &solver.scene_objects[0];
+        assert!(
+            (body.get_position().y - (DEFAULT_BOUNDS.y - radius)).abs() < 1e-3,
+            "{:?}",
+            body.get_position()
+        );
+        assert!(
+            body.get_velocity().length() < 1e-2,
+            "{:?}",
+            body.get_velocity()
+        );
+    }
+
+    #[test]
+    fn polygon_vertex_is_held_by_the_screen_edge() {
+        let mut square = Polygon::rectangle(0.4, 0.4);
+        square.set_angle(0.3);
+        // Lowest corner 0.01 m below the floor.
+        let lowest = square
+            .vertices()
+            .iter()
+            .map(|&vertex| square.to_world(vertex))
+            .max_by(|a, b| a.y.total_cmp(&b.y))
+            .unwrap();
+        square.set_position(Vector2::new(3.2, DEFAULT_BOUNDS.y + 0.01 - lowest.y));
+        let scene: Vec<Box<dyn PhysicsObject>> = vec![Box::new(square)];
+
+        let rows = ScreenEdge::new().rows(&scene, &InputState::new());
+        assert_eq!(rows.len(), 2, "{rows:?}");
+        let normal = &rows[0];
+        assert!((normal.c + 0.01).abs() < 1e-4, "C = {}", normal.c);
+        assert_eq!(normal.bounds, (0.0, Real::INFINITY));
+        // `J = [n̂ᵀ, w × n̂]` with `n̂` pointing up.
+        assert_eq!(normal.entries[0], (1, -1.0));
+        assert_eq!(normal.entries[1].0, 2);
+        assert!((normal.entries[1].1 + lowest.x).abs() < 1e-6);
+        assert_eq!(rows[1].scale_row, Some(0));
+    }
+
+    #[test]
+    fn restitution_scales_the_rebound_speed() {
+        let input = InputState::new();
+        for kind in IntegratorKind::ALL {
+            let mut circle = Circle::new();
+            circle.body.restitution = 0.5;
+            let mut solver = solver_with(vec![Box::new(circle)]);
+            solver.integrator = kind.build();
+            solver.constraints.push(Box::new(ScreenEdge::new()));
+
+            let mut impact_speed = 0.0;
+            let mut rebound_speed = None;
+            for _ in 0..120 {
+                let falling = solver.scene_objects[0].get_velocity().y;
+                solver.step(STEP, SUB_STEPS, &input);
+                let velocity = solver.scene_objects[0].get_velocity().y;
+                if falling > 0.0 && velocity < 0.0 {
+                    impact_speed = falling;
+                    rebound_speed = Some(-velocity);
+                    break;
+                }
+            }
+
+            let name = solver.integrator.name();
+            let rebound_speed =
+                rebound_speed.unwrap_or_else(|| panic!("{name}: the circle never bounced"));
+            let ratio = rebound_speed / impact_speed;
+            assert!((ratio - 0.5).abs() < 0.02, "{name}: rebound ratio {ratio}");
+        }
+    }
+
+    #[test]
+    fn sliding_box_stops_where_kinetic_friction_says() {
+        let speed = 2.0;
+        let mut block = Polygon::rectangle(0.4, 0.2);
+        block.set_position(Vector2::new(1.0, DEFAULT_BOUNDS.y - 0.1));
+        block.set_velocity(Vector2::new(speed, 0.0));
+        let friction = block.get_friction();
+        let gravity = UniformGravity::default().acceleration.y;
+        let mut solver = solver_with(vec![Box::new(block)]);
+        solver.constraints.push(Box::new(ScreenEdge::new()));
+
+        let input = InputState::new();
+        for _ in 0..120 {
+            solver.step(STEP, SUB_STEPS, &input);
+        }
+
+        // `v² = 2 μ_k g d`.
+        let expected = speed * speed / (2.0 * friction.dynamic_coefficient * gravity);
+        let body = &solver.scene_objects[0];
+        let distance = body.get_position().x - 1.0;
+        assert!((distance - expected).abs() < 0.02, "slid {distance} m");
+        assert!(
+            body.get_velocity().x.abs() < 1e-3,
+            "{:?}",
+            body.get_velocity()
+        );
+    }
+
+    #[test]
+    fn thrown_disc_settles_into_rolling() {
+        let speed = 3.0;
+        let circle = Circle::new();
+        let radius = circle.radius;
+        let mut solver = solver_with(vec![Box::new(circle)]);
+        let disc = &mut solver.scene_objects[0];
+        disc.set_position(Vector2::new(1.0, DEFAULT_BOUNDS.y - radius));
+        disc.set_velocity(Vector2::new(speed, 0.0));
+        solver.constraints.push(Box::new(ScreenEdge::new()));
+
+        let input = InputState::new();
+        for _ in 0..60 {
+            solver.step(STEP, SUB_STEPS, &input);
+        }
+
+        // Friction only acts while the rim slips, and the angular momentum
+        // about the contact point is kept, so a uniform disc rolls on at
+        // two thirds of its throwing speed.
+        let body = &solver.scene_objects[0];
+        let velocity = body.get_velocity().x;
+        assert!(
+            (velocity - speed * 2.0 / 3.0).abs() < 0.01,
+            "v = {velocity}"
+        );
+        let rim_speed = body.get_angular_velocity() * radius;
+        assert!((rim_speed - velocity).abs() < 1e-2, "ωr = {rim_speed}");
+    }
+
+    #[test]
+    fn distance_constraint_holds_its_length() {
+        let mut anchor = Circle::new();
+        anchor.set_body_type(BodyType::Static);
+        let mut bob = Circle::new();
+        bob.body.position = anchor.body.position + Vector2::new(1.0, 0.0);
+        let mut solver = solver_with(vec![Box::new(anchor), Box::new(bob)]);
+        solver
+            .constraints
+            .push(Box::new(DistanceConstraint::new(0, 1, 1.0)));
+
+        let input = InputState::new();
+        for _ in 0..600 {
+            solver.step(STEP, SUB_STEPS, &input);
+            let span =
+                solver.scene_objects[1].get_position() - solver.scene_objects[0].get_position();
+            assert!(
+                (span.length() - 1.0).abs() < 1e-3,
+                "length {}",
+                span.length()
+            );
+        }
+    }
+}
@@ -0,0 +1,558 @@
+use crate::physics::broadphase::{Broadphase, BroadphaseKind};
+use crate::physics::constraints::{
+    Baumgarte, Bounces, Constraint, ConstraintRow, RowId, DOF_PER_BODY,
+};
+use crate::physics::input::InputState;
+use crate::physics::math::{Real, Vector2};
+use crate::physics::objects::{Friction, PhysicsObject, Shape};
+
+/// One point where two bodies overlap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+    pub body_a: usize,
+    pub body_b: usize,
+    /// Unit normal pointing from `body_a` towards `body_b`.
+    pub normal: Vector2,
+    /// Signed gap along `normal`, negative while the bodies overlap.
+    pub separation: Real,
+    /// Contact point relative to each body's centre, in world space.
+    pub offset_a: Vector2,
+    pub offset_b: Vector2,
+    /// Which features of the two shapes touch, so the contact keeps its
+    /// identity from one substep to the next. Only meaningful within a pair.
+    pub feature: u32,
+    /// The two surfaces' coefficients combined.
+    pub friction: Friction,
+    /// The bouncier of the two surfaces' restitution.
+    pub restitution: Real,
+}
+
+impl Contact {
+    /// Direction of sliding, `n` turned a quarter turn.
+    pub fn tangent(&self) -> Vector2 {
+        self.normal.perp()
+    }
+
+    /// Row of `J` for the velocity of the contact point on `body_b` relative
+    /// to the one on `body_a`, along `direction`:
+    /// `[-dᵀ, -(r_a × d), dᵀ, r_b × d]` over the two bodies' coordinates.
+    /// Along the normal this is the row of `C = separation ≥ 0`.
+    pub fn jacobian_entries(&self, direction: Vector2) -> [(usize, Real); 6] {
+        let column_a = self.body_a * DOF_PER_BODY;
+        let column_b = self.body_b * DOF_PER_BODY;
+        [
+            (column_a, -direction.x),
+            (column_a + 1, -direction.y),
+            (column_a + 2, -self.offset_a.cross(direction)),
+            (column_b, direction.x),
+            (column_b + 1, direction.y),
+            (column_b + 2, self.offset_b.cross(direction)),
+        ]
+    }
+
+    /// Velocity of the contact point on `body_b` relative to the one on
+    /// `body_a`.
+    pub fn relative_velocity(&self, scene_objects: &[Box<dyn PhysicsObject>]) -> Vector2 {
+        let point_velocity = |body: usize, offset: Vector2| {
+            let ele = &scene_objects[body];
+            ele.get_velocity() + offset.perp() * ele.get_angular_velocity()
+        };
+        point_velocity(self.body_b, self.offset_b) - point_velocity(self.body_a, self.offset_a)
+    }
+}
+
+/// A contact found by one of the shape tests below, in world space, before
+/// it is tied to the bodies. `normal` points from the first shape tested to
+/// the second.
+struct ContactPoint {
+    normal: Vector2,
+    separation: Real,
+    /// Halfway between the two surfaces.
+    point: Vector2,
+    feature: u32,
+}
+
+/// Runs the exact shape test on each candidate pair from a broadphase.
+/// Pairs where neither body can move are skipped, as their rows would be
+/// empty.
+pub fn find_contacts(
+    scene_objects: &[Box<dyn PhysicsObject>],
+    pairs: &[(usize, usize)],
+) -> Vec<Contact> {
+    pairs
+        .iter()
+        .flat_map(|&(body_a, body_b)| contacts_between(scene_objects, body_a, body_b))
+        .collect()
+}
+
+/// Contacts between two bodies, empty unless they overlap. Circles touch at
+/// one point; polygons at up to two.
+pub fn contacts_between(
+    scene_objects: &[Box<dyn PhysicsObject>],
+    body_a: usize,
+    body_b: usize,
+) -> Vec<Contact> {
+    let (a, b) = (
+        scene_objects[body_a].as_ref(),
+        scene_objects[body_b].as_ref(),
+    );
+    if a.get_inverse_mass() == 0.0 && b.get_inverse_mass() == 0.0 {
+        return Vec::new();
+    }
+    let (points, flipped) = match (a.shape(), b.shape()) {
+        (Shape::Circle { radius: radius_a }, Shape::Circle { radius: radius_b }) => (
+            circle_circle(a.get_position(), radius_a, b.get_position(), radius_b),
+            false,
+        ),
+        (Shape::Polygon { .. }, Shape::Circle { radius }) => {
+            (polygon_circle(a, b.get_position(), radius), false)
+        }
+        (Shape::Circle { radius }, Shape::Polygon { .. }) => {
+            (polygon_circle(b, a.get_position(), radius), true)
+        }
+        (Shape::Polygon { .. }, Shape::Polygon { .. }) => (polygon_polygon(a, b), false),
+    };
+    points
+        .into_iter()
+        .map(|contact| Contact {
+            body_a,
+            body_b,
+            normal: if flipped {
+                -contact.normal
+            } else {
+                contact.normal
+            },
+            separation: contact.separation,
+            offset_a: contact.point - a.get_position(),
+            offset_b: contact.point - b.get_position(),
+            feature: contact.feature,
+            friction: a.get_friction().combine(b.get_friction()),
+            restitution: a.get_restitution().max(b.get_restitution()),
+        })
+        .collect()
+}
+
+fn circle_circle(
+    centre_a: Vector2,
+    radius_a: Real,
+    centre_b: Vector2,
+    radius_b: Real,
+) -> Vec<ContactPoint> {
+    let centre_offset = centre_b - centre_a;
+    let distance = centre_offset.length();
+    let separation = distance - (radius_a + radius_b);
+    if separation >= 0.0 {
+        return Vec::new();
+    }
+    // Coincident centres have no preferred direction, so pick one.
+    let normal = if distance > 0.0 {
+        centre_offset / distance
+    } else {
+        Vector2::new(0.0, -1.0)
+    };
+    vec![ContactPoint {
+        normal,
+        separation,
+        point: centre_a + normal * (radius_a + 0.5 * separation),
+        feature: 0,
+    }]
+}
+
+/// World-space vertices of a polygon body.
+fn world_vertices(body: &dyn PhysicsObject) -> Vec<Vector2> {
+    match body.shape() {
+        Shape::Polygon { vertices } => vertices
+            .iter()
+            .map(|&vertex| body.get_position() + body.to_world(vertex))
+            .collect(),
+        Shape::Circle { .. } => vec![body.get_position()],
+    }
+}
+
+/// Outward unit normal of edge `index`, from vertex `index` to the next.
+fn edge_normal(vertices: &[Vector2], index: usize) -> Vector2 {
+    let edge = vertices[(index + 1) % vertices.len()] - vertices[index];
+    Vector2::new(edge.y, -edge.x).normalized()
+}
+
+/// The polygon's edge the circle is furthest outside of decides the
+/// contact: the circle touches either that edge or one of its end vertices,
+/// depending on which Voronoi region its centre is in.
+fn polygon_circle(polygon: &dyn PhysicsObject, centre: Vector2, radius: Real) -> Vec<ContactPoint> {
+    let vertices = world_vertices(polygon);
+    let (face, face_separation) = (0..vertices.len())
+        .map(|index| {
+            (
+                index,
+                edge_normal(&vertices, index).dot(centre - vertices[index]),
+            )
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap();
+    if face_separation > radius {
+        return Vec::new();
+    }
+    let start = vertices[face];
+    let end = vertices[(face + 1) % vertices.len()];
+
+    // Nearest feature of the polygon to the centre, as its surface point
+    // and the normal from there out towards the circle.
+    let (surface, normal, distance, feature) =
+        if face_separation > 0.0 && (centre - start).dot(end - start) < 0.0 {
+            let offset = centre - start;
+            (
+                start,
+                offset.normalized(),
+                offset.length(),
+                2 * face as u32 + 1,
+            )
+        } else if face_separation > 0.0 && (centre - end).dot(start - end) < 0.0 {
+            let offset = centre - end;
+            let vertex = (face + 1) % vertices.len();
+            (
+                end,
+                offset.normalized(),
+                offset.length(),
+                2 * vertex as u32 + 1,
+            )
+        } else {
+            let normal = edge_normal(&vertices, face);
+            (
+                centre - normal * face_separation,
+                normal,
+                face_separation,
+                2 * face as u32,
+            )
+        };
+    let separation = distance - radius;
+    if separation >= 0.0 {
+        return Vec::new();
+    }
+    vec![ContactPoint {
+        normal,
+        separation,
+        point: surface + normal * (0.5 * separation),
+        feature,
+    }]
+}
+
+/// Largest separation of `other` from any edge of `reference`, and that
+/// edge. Positive means the edge is a separating axis.
+fn max_separation(reference: &[Vector2], other: &[Vector2]) -> (usize, Real) {
+    (0..reference.len())
+        .map(|index| {
+            let normal = edge_normal(reference, index);
+            let deepest = other
+                .iter()
+                .map(|&vertex| normal.dot(vertex - reference[index]))
+                .fold(Real::INFINITY, Real::min);
+            (index, deepest)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap()
+}
+
+/// Keeps the part of the segment `points` on the side of the line where
+/// `direction·p ≤ limit`.
+fn clip_segment(points: &[Vector2], direction: Vector2, limit: Real) -> Vec<Vector2> {
+    let distances: Vec<Real> = points
+        .iter()
+        .map(|&point| direction.dot(point) - limit)
+        .collect();
+    let mut clipped: Vec<Vector2> = points
+        .iter()
+        .zip(&distances)
+        .filter(|&(_, &distance)| distance <= 0.0)
+        .map(|(&point, _)| point)
+        .collect();
+    if points.len() == 2 && distances[0] * distances[1] < 0.0 {
+        let t = distances[0] / (distances[0] - distances[1]);
+        clipped.push(points[0] + (points[1] - points[0]) * t);
+    }
+    clipped
+}
+
+/// Separating axis test over the edge normals of both polygons. If none
+/// separates them, the edge of least penetration is the reference face and
+/// the most opposed edge of the other polygon is clipped against its sides,
+/// giving up to two contact points.
+fn polygon_polygon(a: &dyn PhysicsObject, b: &dyn PhysicsObject) -> Vec<ContactPoint> {
+    let vertices_a = world_vertices(a);
+    let vertices_b = world_vertices(b);
+    let (face_a, separation_a) = max_separation(&vertices_a, &vertices_b);
+    if separation_a > 0.0 {
+        return Vec::new();
+    }
+    let (face_b, separation_b) = max_separation(&vertices_b, &vertices_a);
+    if separation_b > 0.0 {
+        return Vec::new();
+    }
+
+    // Prefer a's face unless b's is clearly better, so the choice does not
+    // flicker between near-equal faces.
+    let flipped = separation_b > separation_a + 5e-4;
+    let (reference, incident, face) = if flipped {
+        (&vertices_b, &vertices_a, face_b)
+    } else {
+        (&vertices_a, &vertices_b, face_a)
+    };
+    let normal = edge_normal(reference, face);
+    let start = reference[face];
+    let end = reference[(face + 1) % reference.len()];
+    let tangent = (end - start).normalized();
+
+    let incident_edge = (0..incident.len())
+        .min_by(|&i, &j| {
+            normal
+                .dot(edge_normal(incident, i))
+                .total_cmp(&normal.dot(edge_normal(incident, j)))
+        })
+        .unwrap();
+    let segment = [
+        incident[incident_edge],
+        incident[(incident_edge + 1) % incident.len()],
+    ];
+    let clipped = clip_segment(&segment, -tangent, -tangent.dot(start));
+    let clipped = clip_segment(&clipped, tangent, tangent.dot(end));
+
+    clipped
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, point)| {
+            let separation = normal.dot(point - start);
+            if separation >= 0.0 {
+                return None;
+            }
+            Some(ContactPoint {
+                normal: if flipped { -normal } else { normal },
+                separation,
+                point: point - normal * (0.5 * separation),
+                feature: (flipped as u32) << 17
+                    | (face as u32) << 9
+                    | (incident_edge as u32) << 1
+                    | index as u32,
+            })
+        })
+        .collect()
+}
+
+/// Rows for every contact between bodies: a non-penetration row with
+/// `C = separation ≥ 0` (for two circles, `|p_b - p_a| - (r_a + r_b)`),
+/// followed by a friction row along the tangent bounded by `μ λₙ`. Normal
+/// rows closing fast enough bounce with the contact's restitution.
+///
+/// The friction rows have no position error. Their Baumgarte damping term
+/// asks for the slip at the contact to stop, and the bounds cap the force
+/// that may be spent doing so.
+pub struct BodyContacts {
+    pub baumgarte: Baumgarte,
+    /// Picks the pairs worth testing each substep.
+    pub broadphase: Box<dyn Broadphase>,
+    /// Slowest closing speed, in metres per second, that bounces. See
+    /// `Bounces::apply`.
+    pub restitution_threshold: Real,
+    bounces: Bounces,
+    /// Candidate pairs and contacts found by the last call to `rows`, kept
+    /// for debug views.
+    candidate_pairs: Vec<(usize, usize)>,
+    contacts: Vec<Contact>,
+}
+
+impl BodyContacts {
+    pub fn new() -> Self {
+        Self::with_broadphase(BroadphaseKind::SpatialHash.build())
+    }
+
+    pub fn with_broadphase(broadphase: Box<dyn Broadphase>) -> Self {
+        Self {
+            baumgarte: Baumgarte::default(),
+            broadphase,
+            restitution_threshold: 0.5,
+            bounces: Bounces::default(),
+            candidate_pairs: Vec::new(),
+            contacts: Vec::new(),
+        }
+    }
+
+    /// Pairs the broadphase passed on in the last substep.
+    pub fn candidate_pairs(&self) -> &[(usize, usize)] {
+        &self.candidate_pairs
+    }
+
+    pub fn contacts(&self) -> &[Contact] {
+        &self.contacts
+    }
+}
+
+impl Default for BodyContacts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Constraint for BodyContacts {
+    fn rows(
+        &mut self,
+        scene_objects: &[Box<dyn PhysicsObject>],
+        _input: &InputState,
+    ) -> Vec<ConstraintRow> {
+        self.candidate_pairs = self.broadphase.pairs(scene_objects);
+        self.contacts = find_contacts(scene_objects, &self.candidate_pairs);
+
+        let mut rows = Vec::with_capacity(2 * self.contacts.len());
+        for contact in self.contacts.iter() {
+            let relative_velocity = contact.relative_velocity(scene_objects);
+            let normal_row = rows.len();
+            // Contacts push bodies apart but never pull them together.
+            // Friction may act either way along the tangent, up to μ times
+            // the push.
+            let mut row = ConstraintRow {
+                bounds: (0.0, Real::INFINITY),
+                j_dot_q_dot: normal_jacobian_dot_velocity(scene_objects, contact),
+                ..ConstraintRow::new(
+                    RowId::new(contact.body_a, contact.body_b, 2 * contact.feature),
+                    contact.jacobian_entries(contact.normal).to_vec(),
+                    contact.separation,
+                )
+            };
+            self.bounces.apply(
+                scene_objects,
+                &mut row,
+                contact.restitution,
+                self.restitution_threshold,
+            );
+            rows.push(row);
+            let coefficient = contact
+                .friction
+                .coefficient(relative_velocity.dot(contact.tangent()));
+            rows.push(ConstraintRow {
+                bounds: (-coefficient, coefficient),
+                scale_row: Some(normal_row),
+                ..ConstraintRow::new(
+                    RowId::new(contact.body_a, contact.body_b, 2 * contact.feature + 1),
+                    contact.jacobian_entries(contact.tangent()).to_vec(),
+                    0.0,
+                )
+            });
+        }
+        self.bounces
+            .add_parted(scene_objects, &mut rows, self.restitution_threshold);
+        rows
+    }
+
+    fn baumgarte(&self) -> Baumgarte {
+        self.baumgarte
+    }
+
+    fn begin_substep(&mut self, scene_objects: &[Box<dyn PhysicsObject>]) {
+        self.bounces.begin_substep(scene_objects);
+    }
+}
+
+/// `J̇q̇` of a contact's normal row. Between two circles, with
+/// `d = p_b - p_a`, `C̈ = n·d̈ + |ḋ_t|² / |d|`, where `ḋ_t` is the part of
+/// `ḋ` along the contact tangent. Contacts on a polygon only keep the
+/// centripetal part, `n·(ω_a² r_a - ω_b² r_b)`, and neglect the turning of
+/// the normal. The turning of the tangent is neglected for the friction
+/// rows, whose damping term corrects any slip it causes.
+fn normal_jacobian_dot_velocity(
+    scene_objects: &[Box<dyn PhysicsObject>],
+    contact: &Contact,
+) -> Real {
+    let (a, b) = (
+        &scene_objects[contact.body_a],
+        &scene_objects[contact.body_b],
+    );
+    if let (Shape::Circle { .. }, Shape::Circle { .. }) = (a.shape(), b.shape()) {
+        let distance = (b.get_position() - a.get_position()).length();
+        if distance == 0.0 {
+            return 0.0;
+        }
+        let tangential = (b.get_velocity() - a.get_velocity()).dot(contact.tangent());
+        return tangential * tangential / distance;
+    }
+    let (omega_a, omega_b) = (a.get_angular_velocity(), b.get_angular_velocity());
+    let centripetal =
+        contact.offset_a * (omega_a * omega_a) - contact.offset_b * (omega_b * omega_b);
+    centripetal.dot(contact.normal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::objects::{BodyType, Circle, Polygon};
+
+    /// A 0.4 m square centred on (3.2, 2.4), so its top edge is at y = 2.2
+    /// and its top-left corner at (3.0, 2.2).
+    fn square() -> Box<dyn PhysicsObject> {
+        Box::new(Polygon::rectangle(0.4, 0.4))
+    }
+
+    fn circle_at(centre: Vector2) -> Box<dyn PhysicsObject> {
+        let mut circle = Circle::new();
+        circle.set_position(centre);
+        Box::new(circle)
+    }
+
+    fn assert_close(actual: Vector2, expected: Vector2) {
+        assert!(
+            (actual - expected).length() < 1e-4,
+            "{actual:?} vs {expected:?}"
+        );
+    }
+
+    #[test]
+    fn box_resting_on_box_touches_at_both_corners() {
+        let mut ground = Polygon::rectangle(2.0, 0.5);
+        ground.set_body_type(BodyType::Static);
+        ground.set_position(Vector2::new(3.2, 4.0));
+        let mut block = Polygon::rectangle(0.4, 0.4);
+        // Sunk 0.01 m into the ground's top face at y = 3.75.
+        block.set_position(Vector2::new(3.2, 3.75 - 0.2 + 0.01));
+        let scene: Vec<Box<dyn PhysicsObject>> = vec![Box::new(ground), Box::new(block)];
+
+        let contacts = contacts_between(&scene, 0, 1);
+        assert_eq!(contacts.len(), 2, "{contacts:?}");
+        for contact in &contacts {
+            assert_close(contact.normal, Vector2::new(0.0, -1.0));
+            assert!((contact.separation + 0.01).abs() < 1e-4, "{contact:?}");
+        }
+        let mut corners: Vec<Real> = contacts.iter().map(|contact| contact.offset_b.x).collect();
+        corners.sort_by(Real::total_cmp);
+        assert!((corners[0] + 0.2).abs() < 1e-4 && (corners[1] - 0.2).abs() < 1e-4);
+        assert_ne!(contacts[0].feature, contacts[1].feature);
+    }
+
+    #[test]
+    fn circle_touches_polygon_edge() {
+        let radius = Circle::new().radius;
+        let scene = vec![square(), circle_at(Vector2::new(3.25, 2.2 - radius + 0.01))];
+
+        let contacts = contacts_between(&scene, 0, 1);
+        assert_eq!(contacts.len(), 1);
+        let contact = contacts[0];
+        assert_close(contact.normal, Vector2::new(0.0, -1.0));
+        assert!((contact.separation + 0.01).abs() < 1e-4, "{contact:?}");
+        assert_eq!(contact.feature % 2, 0, "not an edge");
+    }
+
+    #[test]
+    fn circle_touches_polygon_vertex() {
+        let radius = Circle::new().radius;
+        let corner = Vector2::new(3.0, 2.2);
+        let outward = Vector2::new(-1.0, -1.0).normalized();
+        let scene = vec![square(), circle_at(corner + outward * (radius - 0.01))];
+
+        let contacts = contacts_between(&scene, 0, 1);
+        assert_eq!(contacts.len(), 1);
+        let contact = contacts[0];
+        assert_close(contact.normal, outward);
+        assert!((contact.separation + 0.01).abs() < 1e-4, "{contact:?}");
+        assert_eq!(contact.feature % 2, 1, "not a vertex");
+
+        // From the circle's side the normal still points from the first body
+        // to the second.
+        let reversed = contacts_between(&scene, 1, 0);
+        assert_close(reversed[0].normal, -outward);
+    }
+}
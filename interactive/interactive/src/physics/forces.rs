@@ -0,0 +1,284 @@
+use crate::physics::constraints::NO_BODY;
+use crate::physics::math::{Real, Vector2};
+use crate::physics::objects::PhysicsObject;
+
+/// A source of applied force, the `Q` in `[J W Jᵀ]λ = -J̇q̇ - J W Q - bias`.
+///
+/// Generators only see the current state and add to `forces` and `torques`,
+/// one entry per body, before the constraint solve. Constraint forces never
+/// go through here.
+pub trait ForceGenerator {
+    fn name(&self) -> &'static str;
+    fn apply(
+        &mut self,
+        scene_objects: &[Box<dyn PhysicsObject>],
+        forces: &mut [Vector2],
+        torques: &mut [Real],
+    );
+}
+
+/// The same acceleration on every body, `F = m g`.
+pub struct UniformGravity {
+    pub acceleration: Vector2,
+}
+
+impl UniformGravity {
+    pub fn new(acceleration: Vector2) -> Self {
+        Self { acceleration }
+    }
+}
+
+impl Default for UniformGravity {
+    /// Standard gravity, pointing down the screen.
+    fn default() -> Self {
+        Self::new(Vector2::new(0.0, 9.81))
+    }
+}
+
+impl ForceGenerator for UniformGravity {
+    fn name(&self) -> &'static str {
+        "Uniform gravity"
+    }
+
+    fn apply(
+        &mut self,
+        scene_objects: &[Box<dyn PhysicsObject>],
+        forces: &mut [Vector2],
+        _torques: &mut [Real],
+    ) {
+        for (ele, force) in scene_objects.iter().zip(forces.iter_mut()) {
+            *force += self.acceleration * ele.get_mass();
+        }
+    }
+}
+
+/// Viscous drag, `F = -k v`.
+pub struct LinearDrag {
+    /// `k`, in kilograms per second.
+    pub coefficient: Real,
+}
+
+impl LinearDrag {
+    pub fn new(coefficient: Real) -> Self {
+        Self { coefficient }
+    }
+}
+
+impl ForceGenerator for LinearDrag {
+    fn name(&self) -> &'static str {
+        "Linear drag"
+    }
+
+    fn apply(
+        &mut self,
+        scene_objects: &[Box<dyn PhysicsObject>],
+        forces: &mut [Vector2],
+        _torques: &mut [Real],
+    ) {
+        for (ele, force) in scene_objects.iter().zip(forces.iter_mut()) {
+            *force -= ele.get_velocity() * self.coefficient;
+        }
+    }
+}
+
+/// Aerodynamic drag, `F = -k |v| v`.
+pub struct QuadraticDrag {
+    /// `k`, in kilograms per metre. Folds in `½ ρ C_d A`.
+    pub coefficient: Real,
+}
+
+impl QuadraticDrag {
+    pub fn new(coefficient: Real) -> Self {
+        Self { coefficient }
+    }
+}
+
+impl ForceGenerator for QuadraticDrag {
+    fn name(&self) -> &'static str {
+        "Quadratic drag"
+    }
+
+    fn apply(
+        &mut self,
+        scene_objects: &[Box<dyn PhysicsObject>],
+        forces: &mut [Vector2],
+        _torques: &mut [Real],
+    ) {
+        for (ele, force) in scene_objects.iter().zip(forces.iter_mut()) {
+            let velocity = ele.get_velocity();
+            *force -= velocity * (velocity.length() * self.coefficient);
+        }
+    }
+}
+
+/// Inverse-square pull towards a fixed point, `F = μ m / r²`.
+pub struct PointAttractor {
+    pub position: Vector2,
+    /// `μ`, the attracting mass times the gravitational constant, in m³/s².
+    pub strength: Real,
+    /// Distances below this are treated as this, so bodies passing through
+    /// the centre do not get an unbounded kick.
+    pub min_distance: Real,
+}
+
+impl PointAttractor {
+    pub fn new(position: Vector2, strength: Real) -> Self {
+        Self {
+            position,
+            strength,
+            min_distance: 0.1,
+        }
+    }
+}
+
+impl ForceGenerator for PointAttractor {
+    fn name(&self) -> &'static str {
+        "Point attractor"
+    }
+
+    fn apply(
+        &mut self,
+        scene_objects: &[Box<dyn PhysicsObject>],
+        forces: &mut [Vector2],
+        _torques: &mut [Real],
+    ) {
+        for (ele, force) in scene_objects.iter().zip(forces.iter_mut()) {
+            let to_centre = self.position - ele.get_position();
+            let distance = to_centre.length();
+            if distance == 0.0 {
+                continue;
+            }
+            let clamped = distance.max(self.min_distance);
+            *force += to_centre / distance * (self.strength * ele.get_mass() / (clamped * clamped));
+        }
+    }
+}
+
+/// Damped Hookean spring between points on two bodies, or between a point on
+/// a body and a fixed anchor when `body_b` is `NO_BODY`. Springs attached
+/// away from a centre also exert a torque.
+pub struct Spring {
+    pub body_a: usize,
+    pub body_b: usize,
+    /// Attachment point on `body_a`, in its body space.
+    pub local_anchor_a: Vector2,
+    /// Attachment point on `body_b` in its body space, or the world-space
+    /// anchor when `body_b` is `NO_BODY`.
+    pub anchor: Vector2,
+    pub rest_length: Real,
+    /// In newtons per metre.
+    pub stiffness: Real,
+    /// Opposes the rate of stretch, in newton seconds per metre.
+    pub damping: Real,
+}
+
+impl Spring {
+    pub fn new(body_a: usize, body_b: usize, rest_length: Real, stiffness: Real) -> Self {
+        Self {
+            body_a,
+            body_b,
+            local_anchor_a: Vector2::zero(),
+            anchor: Vector2::zero(),
+            rest_length,
+            stiffness,
+            damping: 0.0,
+        }
+    }
+
+    pub fn anchored(body: usize, anchor: Vector2, rest_length: Real, stiffness: Real) -> Self {
+        Self {
+            anchor,
+            ..Self::new(body, NO_BODY, rest_length, stiffness)
+        }
+    }
+}
+
+/// World-space offset from the centre and velocity of the point at `local`
+/// on `body`.
+fn attachment(body: &dyn PhysicsObject, local: Vector2) -> (Vector2, Vector2) {
+    let offset = body.to_world(local);
+    let velocity = body.get_velocity() + offset.perp() * body.get_angular_velocity();
+    (offset, velocity)
+}
+
+impl ForceGenerator for Spring {
+    fn name(&self) -> &'static str {
+        "Spring"
+    }
+
+    fn apply(
+        &mut self,
+        scene_objects: &[Box<dyn PhysicsObject>],
+        forces: &mut [Vector2],
+        torques: &mut [Real],
+    ) {
+        let body = scene_objects[self.body_a].as_ref();
+        let (offset_a, velocity_a) = attachment(body, self.local_anchor_a);
+        let point_a = body.get_position() + offset_a;
+        let (point_b, velocity_b, offset_b) = if self.body_b == NO_BODY {
+            (self.anchor, Vector2::zero(), Vector2::zero())
+        } else {
+            let end = scene_objects[self.body_b].as_ref();
+            let (offset, velocity) = attachment(end, self.anchor);
+            (end.get_position() + offset, velocity, offset)
+        };
+
+        let span = point_b - point_a;
+        let length = span.length();
+        if length == 0.0 {
+            return;
+        }
+        let direction = span / length;
+        let stretch_rate = (velocity_b - velocity_a).dot(direction);
+        let tension = self.stiffness * (length - self.rest_length) + self.damping * stretch_rate;
+
+        let force = direction * tension;
+        forces[self.body_a] += force;
+        torques[self.body_a] += offset_a.cross(force);
+        if self.body_b != NO_BODY {
+            forces[self.body_b] -= force;
+            torques[self.body_b] -= offset_b.cross(force);
+        }
+    }
+}
+
+/// Drag towards the local wind velocity, `F = k (w(p) - v)`, so bodies are
+/// carried along with the air rather than slowed to rest.
+pub struct WindField {
+    /// `k`, in kilograms per second.
+    pub coefficient: Real,
+    /// Wind velocity at a world position.
+    pub velocity: Box<dyn Fn(Vector2) -> Vector2>,
+}
+
+impl WindField {
+    pub fn new(coefficient: Real, velocity: impl Fn(Vector2) -> Vector2 + 'static) -> Self {
+        Self {
+            coefficient,
+            velocity: Box::new(velocity),
+        }
+    }
+
+    /// The same wind everywhere.
+    pub fn uniform(coefficient: Real, velocity: Vector2) -> Self {
+        Self::new(coefficient, move |_| velocity)
+    }
+}
+
+impl ForceGenerator for WindField {
+    fn name(&self) -> &'static str {
+        "Wind field"
+    }
+
+    fn apply(
+        &mut self,
+        scene_objects: &[Box<dyn PhysicsObject>],
+        forces: &mut [Vector2],
+        _torques: &mut [Real],
+    ) {
+        for (ele, force) in scene_objects.iter().zip(forces.iter_mut()) {
+            let relative = (self.velocity)(ele.get_position()) - ele.get_velocity();
+            *force += relative * self.coefficient;
+        }
+    }
+}
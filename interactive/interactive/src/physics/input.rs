@@ -0,0 +1,50 @@
+use crate::physics::math::Vector2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PointerButton {
+    Left,
+    Right,
+    Middle,
+}
+
+/// Snapshot of user input for one frame.
+///
+/// The frontend fills this once per frame and hands it to the solver, so
+/// constraints never talk to a window directly. Tests and replays can build
+/// one by hand to drive a scene with synthetic input.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InputState {
+    /// Pointer position in world units, already unprojected by the
+    /// frontend's camera.
+    pub pointer_position: Vector2,
+    pub buttons_down: Vec<PointerButton>,
+    /// Key codes currently held. Codes follow GLFW/raylib, so printable keys
+    /// are their uppercase ASCII value.
+    pub keys_down: Vec<u32>,
+    /// Active touch points in world units.
+    pub touch_points: Vec<Vector2>,
+}
+
+impl InputState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_button_down(&self, button: PointerButton) -> bool {
+        self.buttons_down.contains(&button)
+    }
+
+    pub fn is_key_down(&self, key: u32) -> bool {
+        self.keys_down.contains(&key)
+    }
+
+    /// Position of the pointer while `button` is held, falling back to the
+    /// first touch point so drags also work on touch screens.
+    pub fn drag_position(&self, button: PointerButton) -> Option<Vector2> {
+        if self.is_button_down(button) {
+            Some(self.pointer_position)
+        } else {
+            self.touch_points.first().copied()
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use crate::physics::math::{Real, Vector2};
+
+/// Position, angle and their rates for one body, the state an `Integrator`
+/// advances.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BodyState {
+    pub position: Vector2,
+    pub velocity: Vector2,
+    pub angle: Real,
+    pub angular_velocity: Real,
+}
+
+/// Second derivative of a body's state.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BodyAcceleration {
+    pub linear: Vector2,
+    pub angular: Real,
+}
+
+/// Evaluates the acceleration of every body (applied forces plus constraint
+/// forces) for a trial state. Integrators with several stages call it once
+/// per stage.
+pub type AccelerationFn<'a> = dyn FnMut(&[BodyState]) -> Vec<BodyAcceleration> + 'a;
+
+/// Advances body states by one substep.
+///
+/// `acceleration` holds the accelerations already evaluated at `state`, so
+/// single-stage methods never need to call `evaluate`.
+pub trait Integrator {
+    fn name(&self) -> &'static str;
+    fn integrate(
+        &mut self,
+        state: &[BodyState],
+        acceleration: &[BodyAcceleration],
+        dt: Real,
+        evaluate: &mut AccelerationFn,
+    ) -> Vec<BodyState>;
+}
+
+kinds! {
+    /// The integrators available in the demos.
+    IntegratorKind builds Integrator {
+        Verlet => Verlet,
+        VelocityVerlet => VelocityVerlet,
+        SemiImplicitEuler => SemiImplicitEuler,
+        ExplicitEuler => ExplicitEuler,
+        RungeKutta4 => RungeKutta4,
+    }
+}
+
+/// Position Verlet, `x' = 2x - x_old + a dt²`, with `x_old` recovered as
+/// `x - v dt`. The velocity it reports is the backward difference
+/// `(x' - x) / dt`, so the next step sees the same `x_old` plain Verlet would.
+///
+/// Because `x_old` is rebuilt from the stored velocity rather than kept,
+/// this steps exactly like `SemiImplicitEuler`.
+pub struct Verlet;
+
+impl Integrator for Verlet {
+    fn name(&self) -> &'static str {
+        "Verlet"
+    }
+
+    fn integrate(
+        &mut self,
+        state: &[BodyState],
+        acceleration: &[BodyAcceleration],
+        dt: Real,
+        _evaluate: &mut AccelerationFn,
+    ) -> Vec<BodyState> {
+        state
+            .iter()
+            .zip(acceleration)
+            .map(|(body, acceleration)| {
+                let old_position = body.position - body.velocity * dt;
+                let position = body.position * 2.0 - old_position + acceleration.linear * dt * dt;
+                let old_angle = body.angle - body.angular_velocity * dt;
+                let angle = body.angle * 2.0 - old_angle + acceleration.angular * dt * dt;
+                BodyState {
+                    position,
+                    velocity: (position - body.position) / dt,
+                    angle,
+                    angular_velocity: (angle - body.angle) / dt,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Velocity Verlet. Takes a second acceleration evaluation at the new
+/// positions to average the velocity update.
+pub struct VelocityVerlet;
+
+impl Integrator for VelocityVerlet {
+    fn name(&self) -> &'static str {
+        "Velocity Verlet"
+    }
+
+    fn integrate(
+        &mut self,
+        state: &[BodyState],
+        acceleration: &[BodyAcceleration],
+        dt: Real,
+        evaluate: &mut AccelerationFn,
+    ) -> Vec<BodyState> {
+        let predicted: Vec<BodyState> = state
+            .iter()
+            .zip(acceleration)
+            .map(|(body, acceleration)| BodyState {
+                position: body.position
+                    + body.velocity * dt
+                    + acceleration.linear * (0.5 * dt * dt),
+                velocity: body.velocity + acceleration.linear * dt,
+                angle: body.angle
+                    + body.angular_velocity * dt
+                    + acceleration.angular * (0.5 * dt * dt),
+                angular_velocity: body.angular_velocity + acceleration.angular * dt,
+            })
+            .collect();
+        let new_acceleration = evaluate(&predicted);
+        predicted
+            .iter()
+            .zip(state)
+            .zip(acceleration.iter().zip(new_acceleration))
+            .map(
+                |((new_body, body), (acceleration, new_acceleration))| BodyState {
+                    position: new_body.position,
+                    velocity: body.velocity
+                        + (acceleration.linear + new_acceleration.linear) * (0.5 * dt),
+                    angle: new_body.angle,
+                    angular_velocity: body.angular_velocity
+                        + (acceleration.angular + new_acceleration.angular) * (0.5 * dt),
+                },
+            )
+            .collect()
+    }
+}
+
+/// Symplectic Euler: update the velocity first, then move with the new
+/// velocity.
+pub struct SemiImplicitEuler;
+
+impl Integrator for SemiImplicitEuler {
+    fn name(&self) -> &'static str {
+        "Semi-implicit Euler"
+    }
+
+    fn integrate(
+        &mut self,
+        state: &[BodyState],
+        acceleration: &[BodyAcceleration],
+        dt: Real,
+        _evaluate: &mut AccelerationFn,
+    ) -> Vec<BodyState> {
+        state
+            .iter()
+            .zip(acceleration)
+            .map(|(body, acceleration)| {
+                let velocity = body.velocity + acceleration.linear * dt;
+                let angular_velocity = body.angular_velocity + acceleration.angular * dt;
+                BodyState {
+                    position: body.position + velocity * dt,
+                    velocity,
+                    angle: body.angle + angular_velocity * dt,
+                    angular_velocity,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Forward Euler: move with the old velocity, then update it. Gains energy
+/// every step, which is what the stability comparison in the paper shows.
+pub struct ExplicitEuler;
+
+impl Integrator for ExplicitEuler {
+    fn name(&self) -> &'static str {
+        "Explicit Euler"
+    }
+
+    fn integrate(
+        &mut self,
+        state: &[BodyState],
+        acceleration: &[BodyAcceleration],
+        dt: Real,
+        _evaluate: &mut AccelerationFn,
+    ) -> Vec<BodyState> {
+        state
+            .iter()
+            .zip(acceleration)
+            .map(|(body, acceleration)| BodyState {
+                position: body.position + body.velocity * dt,
+                velocity: body.velocity + acceleration.linear * dt,
+                angle: body.angle + body.angular_velocity * dt,
+                angular_velocity: body.angular_velocity + acceleration.angular * dt,
+            })
+            .collect()
+    }
+}
+
+/// Classical fourth order Runge–Kutta, re-evaluating forces and constraints
+/// at each of its four stages.
+pub struct RungeKutta4;
+
+impl RungeKutta4 {
+    /// `state + slope dt`, where `slope` is a derivative packed into a
+    /// `BodyState`.
+    fn offset(state: &[BodyState], slope: &[BodyState], dt: Real) -> Vec<BodyState> {
+        state
+            .iter()
+            .zip(slope)
+            .map(|(body, slope)| BodyState {
+                position: body.position + slope.position * dt,
+                velocity: body.velocity + slope.velocity * dt,
+                angle: body.angle + slope.angle * dt,
+                angular_velocity: body.angular_velocity + slope.angular_velocity * dt,
+            })
+            .collect()
+    }
+
+    /// Derivative of `state` given its accelerations.
+    fn slope(state: &[BodyState], acceleration: &[BodyAcceleration]) -> Vec<BodyState> {
+        state
+            .iter()
+            .zip(acceleration)
+            .map(|(body, acceleration)| BodyState {
+                position: body.velocity,
+                velocity: acceleration.linear,
+                angle: body.angular_velocity,
+                angular_velocity: acceleration.angular,
+            })
+            .collect()
+    }
+}
+
+impl Integrator for RungeKutta4 {
+    fn name(&self) -> &'static str {
+        "Runge-Kutta 4"
+    }
+
+    fn integrate(
+        &mut self,
+        state: &[BodyState],
+        acceleration: &[BodyAcceleration],
+        dt: Real,
+        evaluate: &mut AccelerationFn,
+    ) -> Vec<BodyState> {
+        let k1 = Self::slope(state, acceleration);
+        let stage = Self::offset(state, &k1, 0.5 * dt);
+        let k2 = Self::slope(&stage, &evaluate(&stage));
+        let stage = Self::offset(state, &k2, 0.5 * dt);
+        let k3 = Self::slope(&stage, &evaluate(&stage));
+        let stage = Self::offset(state, &k3, dt);
+        let k4 = Self::slope(&stage, &evaluate(&stage));
+
+        // The weighted sum of the four slopes, packed like the slopes are.
+        let weighted: Vec<BodyState> = (0..state.len())
+            .map(|index| {
+                let slopes = [k1[index], k2[index], k3[index], k4[index]];
+                let mut sum = BodyState::default();
+                for (slope, weight) in slopes.iter().zip([1.0, 2.0, 2.0, 1.0]) {
+                    sum.position += slope.position * weight;
+                    sum.velocity += slope.velocity * weight;
+                    sum.angle += slope.angle * weight;
+                    sum.angular_velocity += slope.angular_velocity * weight;
+                }
+                sum
+            })
+            .collect();
+        Self::offset(state, &weighted, dt / 6.0)
+    }
+}
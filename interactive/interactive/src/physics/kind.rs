@@ -0,0 +1,31 @@
+//! The `*Kind` enums that let frontends pick an implementation of one of
+//! the solver's pluggable parts from a menu or by cycling through them.
+
+/// Declares `$kind`, naming each implementation of `$trait` the demos offer,
+/// with `ALL` in the given order, `build` to construct one, and `next` to
+/// cycle through them.
+macro_rules! kinds {
+    ($(#[$meta:meta])* $kind:ident builds $trait:path { $($variant:ident => $build:expr,)+ }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum $kind {
+            $($variant,)+
+        }
+
+        impl $kind {
+            pub const ALL: &'static [$kind] = &[$($kind::$variant,)+];
+
+            pub fn build(self) -> Box<dyn $trait> {
+                match self {
+                    $($kind::$variant => Box::new($build),)+
+                }
+            }
+
+            /// The kind after this one in `ALL`, wrapping around.
+            pub fn next(self) -> Self {
+                let index = Self::ALL.iter().position(|&kind| kind == self).unwrap();
+                Self::ALL[(index + 1) % Self::ALL.len()]
+            }
+        }
+    };
+}
@@ -0,0 +1,514 @@
+use ndarray::{Array1, Array2};
+
+use crate::physics::math::Real;
+use crate::physics::sparse::SystemMatrix;
+
+/// Per-row limits on λ, gathered from `Constraint::lambda_bounds`.
+///
+/// A row with a `scale_rows` entry has its limits multiplied by the current
+/// λ of that row, which is how friction is held to `μ λₙ`.
+#[derive(Debug, Clone, Default)]
+pub struct LambdaBounds {
+    pub lower: Array1<Real>,
+    pub upper: Array1<Real>,
+    pub scale_rows: Vec<Option<usize>>,
+}
+
+impl LambdaBounds {
+    /// Fixed bounds on every row.
+    pub fn from_pairs(pairs: &[(Real, Real)]) -> Self {
+        Self::with_scale_rows(pairs, vec![None; pairs.len()])
+    }
+
+    pub fn with_scale_rows(pairs: &[(Real, Real)], scale_rows: Vec<Option<usize>>) -> Self {
+        Self {
+            lower: pairs.iter().map(|&(lower, _)| lower).collect(),
+            upper: pairs.iter().map(|&(_, upper)| upper).collect(),
+            scale_rows,
+        }
+    }
+
+    /// Clamps `value` into the bounds of `row`, given the current `lambda`
+    /// of every row.
+    pub fn clamp(&self, row: usize, value: Real, lambda: &Array1<Real>) -> Real {
+        match self.scale_rows[row] {
+            Some(scale_row) => {
+                let scale = lambda[scale_row].abs();
+                value
+                    .max(self.lower[row] * scale)
+                    .min(self.upper[row] * scale)
+            }
+            None => value.max(self.lower[row]).min(self.upper[row]),
+        }
+    }
+}
+
+/// Result of one linear solve.
+#[derive(Debug, Clone)]
+pub struct LinearSolution {
+    pub x: Array1<Real>,
+    /// Iterations (or sweeps) the solver used. Direct solvers report 1.
+    pub iterations: usize,
+    /// Norm of `right - left x` at the end. For projected solvers this
+    /// includes rows held at a bound, so it need not reach zero.
+    pub residual: Real,
+    /// Whether the solver met its tolerance before running out of
+    /// iterations.
+    pub converged: bool,
+}
+
+/// Solves the λ system `[J W Jᵀ]λ = right`.
+///
+/// Implementations are swapped on `Solver::linear_solver` at runtime so the
+/// demos can compare their accuracy and cost on the same scene.
+pub trait LinearSolver {
+    fn name(&self) -> &'static str;
+    /// Solves `left x = right`, using `initial` as the starting guess where
+    /// the method is iterative. Solvers that cannot handle inequalities
+    /// ignore `bounds` and treat every row as an equality.
+    fn solve(
+        &mut self,
+        left: &SystemMatrix,
+        right: &Array1<Real>,
+        bounds: &LambdaBounds,
+        initial: &Array1<Real>,
+    ) -> LinearSolution;
+}
+
+kinds! {
+    /// The linear solvers available in the demos.
+    LinearSolverKind builds LinearSolver {
+        ConjugateGradient => ConjugateGradient::new(),
+        JacobiConjugateGradient => JacobiConjugateGradient::new(),
+        GaussSeidel => GaussSeidel::new(),
+        DenseLu => DenseLu::new(),
+    }
+}
+
+fn norm(vector: &Array1<Real>) -> Real {
+    vector.dot(vector).sqrt()
+}
+
+/// Whether `direction·A direction`, given as `curvature`, is clearly
+/// positive relative to the length of `direction`. Fails for NaN.
+fn has_curvature(curvature: Real, direction: &Array1<Real>, tolerance: Real) -> bool {
+    curvature > tolerance * direction.dot(direction)
+}
+
+pub struct ConjugateGradient {
+    pub max_iterations: usize,
+    pub tolerance: Real,
+}
+
+impl ConjugateGradient {
+    pub fn new() -> Self {
+        Self {
+            max_iterations: 1000,
+            tolerance: Real::EPSILON,
+        }
+    }
+}
+
+impl Default for ConjugateGradient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LinearSolver for ConjugateGradient {
+    fn name(&self) -> &'static str {
+        "Conjugate gradient"
+    }
+
+    fn solve(
+        &mut self,
+        left: &SystemMatrix,
+        right: &Array1<Real>,
+        _bounds: &LambdaBounds,
+        initial: &Array1<Real>,
+    ) -> LinearSolution {
+        // Reimplementation of the Wikipedia conugate gradient code from https://github.com/ange-yaghi/simple-2d-constraint-solver/blob/master/src/conjugate_gradient_sle_solver.cpp
+        // Initialize necessary values at k = 0
+        let mut residual: Array1<Real> = right - left.apply(initial);
+        let mut search_direction = residual.clone();
+        let mut old_resid_norm = norm(&residual);
+        let mut x = initial.clone();
+        let mut iteration_count = 0;
+        // On a singular system whose right side is not quite consistent, the
+        // iterates can run off along the null space, so the best one seen is
+        // what gets returned.
+        let mut best = (old_resid_norm, x.clone());
+
+        while iteration_count < self.max_iterations && old_resid_norm > self.tolerance {
+            let left_search_direction = left.apply(&search_direction);
+            // `J W Jᵀ` is only semi-definite: redundant rows, such as a box
+            // resting on two vertices under another box, leave directions
+            // with no curvature. Stepping along one would divide by zero, so
+            // give up and report the solve as unconverged instead.
+            let curvature = search_direction.dot(&left_search_direction);
+            if !has_curvature(curvature, &search_direction, self.tolerance) {
+                break;
+            }
+            let step_size: Real = old_resid_norm.powi(2) / curvature;
+            x = x + step_size * &search_direction;
+            residual = residual - step_size * left_search_direction;
+            let new_resid_norm = norm(&residual);
+            if new_resid_norm < best.0 {
+                best = (new_resid_norm, x.clone());
+            }
+            search_direction =
+                &residual + (new_resid_norm / old_resid_norm).powi(2) * search_direction;
+            old_resid_norm = new_resid_norm;
+            iteration_count += 1;
+        }
+        let (residual_norm, x) = best;
+        LinearSolution {
+            x,
+            iterations: iteration_count,
+            residual: residual_norm,
+            converged: residual_norm <= self.tolerance,
+        }
+    }
+}
+
+/// Conjugate gradient preconditioned with the diagonal of `J W Jᵀ`.
+pub struct JacobiConjugateGradient {
+    pub max_iterations: usize,
+    pub tolerance: Real,
+}
+
+impl JacobiConjugateGradient {
+    pub fn new() -> Self {
+        Self {
+            max_iterations: 1000,
+            tolerance: Real::EPSILON,
+        }
+    }
+}
+
+impl Default for JacobiConjugateGradient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LinearSolver for JacobiConjugateGradient {
+    fn name(&self) -> &'static str {
+        "Jacobi preconditioned CG"
+    }
+
+    fn solve(
+        &mut self,
+        left: &SystemMatrix,
+        right: &Array1<Real>,
+        _bounds: &LambdaBounds,
+        initial: &Array1<Real>,
+    ) -> LinearSolution {
+        // Rows with an empty diagonal have no effect on any body, so they are
+        // left unpreconditioned.
+        let inverse_diagonal = left
+            .diagonal()
+            .mapv(|value| if value > 0.0 { 1.0 / value } else { 1.0 });
+
+        let mut x = initial.clone();
+        let mut residual: Array1<Real> = right - left.apply(initial);
+        let mut preconditioned = &inverse_diagonal * &residual;
+        let mut search_direction = preconditioned.clone();
+        let mut residual_dot = residual.dot(&preconditioned);
+        let mut iteration_count = 0;
+        // Keeps the best iterate, as `ConjugateGradient` does.
+        let mut best = (norm(&residual), x.clone());
+
+        while iteration_count < self.max_iterations && best.0 > self.tolerance {
+            let left_search_direction = left.apply(&search_direction);
+            // Stops on directions without curvature, as `ConjugateGradient`
+            // does, and once the preconditioned residual vanishes.
+            let curvature = search_direction.dot(&left_search_direction);
+            if !has_curvature(curvature, &search_direction, self.tolerance) || residual_dot <= 0.0 {
+                break;
+            }
+            let step_size = residual_dot / curvature;
+            x = x + step_size * &search_direction;
+            residual = residual - step_size * left_search_direction;
+            let residual_norm = norm(&residual);
+            if residual_norm < best.0 {
+                best = (residual_norm, x.clone());
+            }
+            preconditioned = &inverse_diagonal * &residual;
+            let new_residual_dot = residual.dot(&preconditioned);
+            search_direction =
+                &preconditioned + (new_residual_dot / residual_dot) * search_direction;
+            residual_dot = new_residual_dot;
+            iteration_count += 1;
+        }
+        let (residual_norm, x) = best;
+        LinearSolution {
+            x,
+            iterations: iteration_count,
+            residual: residual_norm,
+            converged: residual_norm <= self.tolerance,
+        }
+    }
+}
+
+/// Projected Gauss–Seidel sweeps over the rows of `J W Jᵀ`.
+///
+/// Sweeping stops once no λ changes by more than `tolerance` relative to
+/// the largest λ.
+///
+/// Each λ is clamped to its bounds as soon as it is updated, which makes
+/// this the solver to use for contacts, friction and other inequality rows.
+/// With unbounded rows it is plain Gauss–Seidel.
+///
+/// Rather than forming the matrix, it keeps `W Jᵀ x` up to date as each
+/// λ changes, so one sweep costs about as much as one product with `J`.
+pub struct GaussSeidel {
+    pub max_iterations: usize,
+    pub tolerance: Real,
+}
+
+impl GaussSeidel {
+    pub fn new() -> Self {
+        Self {
+            max_iterations: 100,
+            tolerance: 1e-6,
+        }
+    }
+}
+
+impl Default for GaussSeidel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LinearSolver for GaussSeidel {
+    fn name(&self) -> &'static str {
+        "Projected Gauss-Seidel"
+    }
+
+    fn solve(
+        &mut self,
+        left: &SystemMatrix,
+        right: &Array1<Real>,
+        bounds: &LambdaBounds,
+        initial: &Array1<Real>,
+    ) -> LinearSolution {
+        let diagonal = left.diagonal();
+        let mut x = Array1::from_shape_fn(initial.len(), |row| {
+            bounds.clamp(row, initial[row], initial)
+        });
+        let mut impulse = left.jacobian.transpose_mul_vec(&x) * left.inverse_mass;
+
+        let mut iterations = 0;
+        let mut converged = false;
+        while iterations < self.max_iterations {
+            iterations += 1;
+            let mut largest_change: Real = 0.0;
+            for row in 0..left.size() {
+                if diagonal[row] <= 0.0 {
+                    continue;
+                }
+                let row_product: Real = left
+                    .jacobian
+                    .row(row)
+                    .map(|(column, value)| value * impulse[column])
+                    .sum();
+                let unclamped = x[row] + (right[row] - row_product) / diagonal[row];
+                let change = bounds.clamp(row, unclamped, &x) - x[row];
+                x[row] += change;
+                for (column, value) in left.jacobian.row(row) {
+                    impulse[column] += left.inverse_mass[column] * value * change;
+                }
+                largest_change = largest_change.max(change.abs());
+            }
+            let largest_lambda = x
+                .iter()
+                .fold(1.0, |largest: Real, value| largest.max(value.abs()));
+            if largest_change <= self.tolerance * largest_lambda {
+                converged = true;
+                break;
+            }
+        }
+        let residual = norm(&(right - left.apply(&x)));
+        LinearSolution {
+            x,
+            iterations,
+            residual,
+            converged,
+        }
+    }
+}
+
+/// Dense LU factorisation with partial pivoting. Far too slow for large
+/// scenes, but exact up to rounding, so it serves as the reference the
+/// iterative solvers are measured against.
+pub struct DenseLu {
+    /// Pivots smaller than this fraction of the largest diagonal entry are
+    /// treated as zero and their row dropped, since `J W Jᵀ` is singular
+    /// whenever two rows are redundant and rounding rarely leaves an exact
+    /// zero behind.
+    pub pivot_tolerance: Real,
+    /// Largest residual norm still reported as converged. Dropped rows can
+    /// leave the system unsolved, and near-zero pivots can blow it up.
+    pub tolerance: Real,
+}
+
+impl DenseLu {
+    pub fn new() -> Self {
+        Self {
+            pivot_tolerance: Real::EPSILON.sqrt(),
+            tolerance: 1e-3,
+        }
+    }
+}
+
+impl Default for DenseLu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LinearSolver for DenseLu {
+    fn name(&self) -> &'static str {
+        "Dense LU"
+    }
+
+    fn solve(
+        &mut self,
+        left: &SystemMatrix,
+        right: &Array1<Real>,
+        _bounds: &LambdaBounds,
+        _initial: &Array1<Real>,
+    ) -> LinearSolution {
+        let size = left.size();
+        let mut matrix = Array2::<Real>::zeros((size, size));
+        for column in 0..size {
+            let mut unit = Array1::<Real>::zeros(size);
+            unit[column] = 1.0;
+            matrix.column_mut(column).assign(&left.apply(&unit));
+        }
+        let mut rhs = right.clone();
+        let smallest_pivot = self.pivot_tolerance
+            * matrix
+                .diag()
+                .iter()
+                .fold(0.0, |largest: Real, value| largest.max(value.abs()));
+
+        // Forward elimination
+        for pivot in 0..size {
+            let best_row = (pivot..size)
+                .max_by(|&a, &b| {
+                    matrix[(a, pivot)]
+                        .abs()
+                        .total_cmp(&matrix[(b, pivot)].abs())
+                })
+                .unwrap();
+            if matrix[(best_row, pivot)].abs() <= smallest_pivot {
+                continue;
+            }
+            if best_row != pivot {
+                for column in 0..size {
+                    matrix.swap((pivot, column), (best_row, column));
+                }
+                rhs.swap(pivot, best_row);
+            }
+            for row in pivot + 1..size {
+                let factor = matrix[(row, pivot)] / matrix[(pivot, pivot)];
+                if factor == 0.0 {
+                    continue;
+                }
+                for column in pivot..size {
+                    matrix[(row, column)] -= factor * matrix[(pivot, column)];
+                }
+                rhs[row] -= factor * rhs[pivot];
+            }
+        }
+
+        // Back substitution
+        let mut x = Array1::<Real>::zeros(size);
+        for row in (0..size).rev() {
+            if matrix[(row, row)].abs() <= smallest_pivot {
+                continue;
+            }
+            let mut sum = rhs[row];
+            for column in row + 1..size {
+                sum -= matrix[(row, column)] * x[column];
+            }
+            x[row] = sum / matrix[(row, row)];
+        }
+        let residual = norm(&(right - left.apply(&x)));
+        LinearSolution {
+            x,
+            iterations: 1,
+            residual,
+            converged: residual <= self.tolerance,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::sparse::SparseJacobian;
+
+    #[test]
+    fn dense_lu_solves_a_known_system() {
+        // With unit masses `J W Jᵀ = J Jᵀ = [[5, 5], [5, 10]]`, and
+        // `λ = (1, 2)` gives `(15, 25)`.
+        let mut jacobian = SparseJacobian::new(3);
+        jacobian.push_row(&[(0, 2.0), (1, 1.0)]);
+        jacobian.push_row(&[(0, 1.0), (1, 3.0)]);
+        let inverse_mass = Array1::ones(3);
+        let left = SystemMatrix {
+            jacobian: &jacobian,
+            inverse_mass: &inverse_mass,
+        };
+        let right = Array1::from(vec![15.0, 25.0]);
+        let bounds = LambdaBounds::from_pairs(&[(Real::NEG_INFINITY, Real::INFINITY); 2]);
+
+        let solution = DenseLu::new().solve(&left, &right, &bounds, &Array1::zeros(2));
+
+        assert!(solution.converged);
+        assert!((solution.x[0] - 1.0).abs() < 1e-4, "{}", solution.x);
+        assert!((solution.x[1] - 2.0).abs() < 1e-4, "{}", solution.x);
+    }
+
+    #[test]
+    fn gauss_seidel_clamps_to_bounds() {
+        // The same `J Jᵀ = [[5, 5], [5, 10]]` as above.
+        let mut jacobian = SparseJacobian::new(3);
+        jacobian.push_row(&[(0, 2.0), (1, 1.0)]);
+        jacobian.push_row(&[(0, 1.0), (1, 3.0)]);
+        let inverse_mass = Array1::ones(3);
+        let left = SystemMatrix {
+            jacobian: &jacobian,
+            inverse_mass: &inverse_mass,
+        };
+        let mut solver = GaussSeidel::new();
+
+        // Unbounded, `(-5, 10)` needs `λ = (-4, 3)`. Held to `λ₀ ≥ 0`, the
+        // first row stops pushing and the second makes up the rest alone.
+        let right = Array1::from(vec![-5.0, 10.0]);
+        let bounds = LambdaBounds::from_pairs(&[
+            (0.0, Real::INFINITY),
+            (Real::NEG_INFINITY, Real::INFINITY),
+        ]);
+        let solution = solver.solve(&left, &right, &bounds, &Array1::zeros(2));
+        assert!(solution.converged);
+        assert!(solution.x[0].abs() < 1e-4, "{}", solution.x);
+        assert!((solution.x[1] - 1.0).abs() < 1e-4, "{}", solution.x);
+
+        // Unbounded, `(15, 25)` needs `λ = (1, 2)`. With the second row held
+        // to half the first, like friction, it settles at `λ₁ = λ₀ / 2`.
+        let right = Array1::from(vec![15.0, 25.0]);
+        let bounds = LambdaBounds::with_scale_rows(
+            &[(0.0, Real::INFINITY), (-0.5, 0.5)],
+            vec![None, Some(0)],
+        );
+        let solution = solver.solve(&left, &right, &bounds, &Array1::zeros(2));
+        assert!(solution.converged);
+        assert!((solution.x[0] - 2.0).abs() < 1e-4, "{}", solution.x);
+        assert!((solution.x[1] - 1.0).abs() < 1e-4, "{}", solution.x);
+    }
+}
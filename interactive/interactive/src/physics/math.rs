@@ -0,0 +1,160 @@
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// Scalar type of the physics core: `f32` by default, `f64` with the `f64`
+/// feature for high-precision reference runs.
+#[cfg(not(feature = "f64"))]
+pub type Real = f32;
+/// Scalar type of the physics core: `f32` by default, `f64` with the `f64`
+/// feature for high-precision reference runs.
+#[cfg(feature = "f64")]
+pub type Real = f64;
+
+/// Plain 2D vector used by the physics core. It mirrors the parts of
+/// `raylib::math::Vector2` the solver relies on so the simulation can run
+/// without linking raylib.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Vector2 {
+    pub x: Real,
+    pub y: Real,
+}
+
+impl Vector2 {
+    pub const fn new(x: Real, y: Real) -> Self {
+        Self { x, y }
+    }
+
+    pub const fn zero() -> Self {
+        Self { x: 0.0, y: 0.0 }
+    }
+
+    pub fn length(&self) -> Real {
+        self.length_sqr().sqrt()
+    }
+
+    pub fn length_sqr(&self) -> Real {
+        self.x * self.x + self.y * self.y
+    }
+
+    pub fn dot(&self, v: Vector2) -> Real {
+        self.x * v.x + self.y * v.y
+    }
+
+    /// 2D cross product `self × v`, the z component of the 3D one.
+    pub fn cross(&self, v: Vector2) -> Real {
+        self.x * v.y - self.y * v.x
+    }
+
+    /// `self` turned a quarter turn towards positive angles. A point at
+    /// offset `r` on a body spinning at `ω` moves at `ω r.perp()`.
+    pub fn perp(&self) -> Vector2 {
+        Vector2::new(-self.y, self.x)
+    }
+
+    /// `self` rotated by `angle` radians.
+    pub fn rotated(&self, angle: Real) -> Vector2 {
+        let (sin, cos) = angle.sin_cos();
+        Vector2::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+    }
+
+    pub fn normalized(&self) -> Vector2 {
+        let length = self.length();
+        if length == 0.0 {
+            *self
+        } else {
+            *self / length
+        }
+    }
+}
+
+impl Add for Vector2 {
+    type Output = Vector2;
+    fn add(self, v: Vector2) -> Vector2 {
+        Vector2::new(self.x + v.x, self.y + v.y)
+    }
+}
+
+impl AddAssign for Vector2 {
+    fn add_assign(&mut self, v: Vector2) {
+        *self = *self + v;
+    }
+}
+
+impl Sub for Vector2 {
+    type Output = Vector2;
+    fn sub(self, v: Vector2) -> Vector2 {
+        Vector2::new(self.x - v.x, self.y - v.y)
+    }
+}
+
+impl SubAssign for Vector2 {
+    fn sub_assign(&mut self, v: Vector2) {
+        *self = *self - v;
+    }
+}
+
+impl Mul for Vector2 {
+    type Output = Vector2;
+    fn mul(self, v: Vector2) -> Vector2 {
+        Vector2::new(self.x * v.x, self.y * v.y)
+    }
+}
+
+impl Mul<Real> for Vector2 {
+    type Output = Vector2;
+    fn mul(self, value: Real) -> Vector2 {
+        Vector2::new(self.x * value, self.y * value)
+    }
+}
+
+impl MulAssign for Vector2 {
+    fn mul_assign(&mut self, v: Vector2) {
+        *self = *self * v;
+    }
+}
+
+impl MulAssign<Real> for Vector2 {
+    fn mul_assign(&mut self, value: Real) {
+        *self = *self * value;
+    }
+}
+
+impl Div for Vector2 {
+    type Output = Vector2;
+    fn div(self, v: Vector2) -> Vector2 {
+        Vector2::new(self.x / v.x, self.y / v.y)
+    }
+}
+
+impl Div<Real> for Vector2 {
+    type Output = Vector2;
+    fn div(self, value: Real) -> Vector2 {
+        Vector2::new(self.x / value, self.y / value)
+    }
+}
+
+impl DivAssign<Real> for Vector2 {
+    fn div_assign(&mut self, value: Real) {
+        *self = *self / value;
+    }
+}
+
+impl Neg for Vector2 {
+    type Output = Vector2;
+    fn neg(self) -> Vector2 {
+        Vector2::new(-self.x, -self.y)
+    }
+}
+
+#[cfg(feature = "raylib")]
+impl From<raylib::math::Vector2> for Vector2 {
+    fn from(v: raylib::math::Vector2) -> Self {
+        Self::new(v.x as Real, v.y as Real)
+    }
+}
+
+#[cfg(feature = "raylib")]
+impl From<Vector2> for raylib::math::Vector2 {
+    fn from(v: Vector2) -> Self {
+        Self::new(v.x as f32, v.y as f32)
+    }
+}
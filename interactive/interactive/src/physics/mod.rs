@@ -0,0 +1,37 @@
+//! Headless physics core. Nothing in here depends on raylib, so scenes can be
+//! stepped without a window or GL context.
+//!
+//! Quantities are in SI units: metres, seconds and kilograms, with `y`
+//! pointing down. Frontends map world space to the screen with their own
+//! camera.
+
+#[macro_use]
+mod kind;
+
+pub mod broadphase;
+pub mod constraints;
+pub mod contacts;
+pub mod forces;
+pub mod input;
+pub mod integrators;
+pub mod linear;
+pub mod math;
+pub mod objects;
+pub mod report;
+pub mod solver;
+pub mod sparse;
+pub mod timestep;
+
+pub use broadphase::*;
+pub use constraints::*;
+pub use contacts::*;
+pub use forces::*;
+pub use input::{InputState, PointerButton};
+pub use integrators::*;
+pub use linear::*;
+pub use math::{Real, Vector2};
+pub use objects::*;
+pub use report::{PhaseTimings, StepReport};
+pub use solver::Solver;
+pub use sparse::{SparseJacobian, SystemMatrix};
+pub use timestep::FixedTimestep;
@@ -0,0 +1,440 @@
+use crate::physics::math::{Real, Vector2};
+
+/// Geometry of a body, used for collision and by frontends to decide how
+/// to draw it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shape<'a> {
+    Circle {
+        radius: Real,
+    },
+    /// Convex polygon with its vertices in body space around the centre of
+    /// mass, wound so that `(e.y, -e.x)` is the outward normal of each edge
+    /// `e = v[i + 1] - v[i]`.
+    Polygon {
+        vertices: &'a [Vector2],
+    },
+}
+
+/// How a body responds to forces.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BodyType {
+    /// Moved by applied and constraint forces.
+    #[default]
+    Dynamic,
+    /// Never moves. Acts as an infinitely heavy anchor or wall.
+    Static,
+    /// Moves with whatever velocity it is given, e.g. by a script, but is
+    /// not pushed by forces or constraints.
+    Kinematic,
+}
+
+/// Below this slip speed, in metres per second, a contact counts as
+/// sticking and uses the static coefficient.
+pub const STATIC_FRICTION_SPEED: Real = 0.01;
+
+/// Coulomb friction coefficients of a body's surface.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Friction {
+    /// `μ_s`, limiting the tangential force while the surfaces stick.
+    pub static_coefficient: Real,
+    /// `μ_k`, limiting it once they slide.
+    pub dynamic_coefficient: Real,
+}
+
+impl Friction {
+    pub fn new(static_coefficient: Real, dynamic_coefficient: Real) -> Self {
+        Self {
+            static_coefficient,
+            dynamic_coefficient,
+        }
+    }
+
+    /// No friction at all.
+    pub fn frictionless() -> Self {
+        Self::new(0.0, 0.0)
+    }
+
+    /// Coefficients for a contact between two surfaces, the geometric mean
+    /// of each, so a frictionless surface is frictionless against anything.
+    pub fn combine(self, other: Friction) -> Self {
+        Self::new(
+            (self.static_coefficient * other.static_coefficient).sqrt(),
+            (self.dynamic_coefficient * other.dynamic_coefficient).sqrt(),
+        )
+    }
+
+    /// The coefficient that applies at `slip_speed`.
+    pub fn coefficient(&self, slip_speed: Real) -> Real {
+        if slip_speed.abs() < STATIC_FRICTION_SPEED {
+            self.static_coefficient
+        } else {
+            self.dynamic_coefficient
+        }
+    }
+}
+
+impl Default for Friction {
+    fn default() -> Self {
+        Self::new(0.6, 0.4)
+    }
+}
+
+/// State every body has whatever its shape: how it responds to forces,
+/// where it is and how it moves, and its surface.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RigidBody {
+    pub body_type: BodyType,
+    /// Inverse of the mass the body has while dynamic.
+    pub inverse_mass: Real,
+    pub position: Vector2,
+    pub velocity: Vector2,
+    pub acceleration: Vector2,
+    pub angle: Real,
+    pub angular_velocity: Real,
+    pub angular_acceleration: Real,
+    pub friction: Friction,
+    pub restitution: Real,
+}
+
+impl RigidBody {
+    /// A dynamic body of unit mass at rest in the middle of the default
+    /// bounds.
+    pub fn new() -> Self {
+        Self {
+            body_type: BodyType::Dynamic,
+            inverse_mass: 1.0,
+            position: Vector2::new(3.2, 2.4),
+            velocity: Vector2::zero(),
+            acceleration: Vector2::zero(),
+            angle: 0.0,
+            angular_velocity: 0.0,
+            angular_acceleration: 0.0,
+            friction: Friction::default(),
+            restitution: 0.5,
+        }
+    }
+}
+
+impl Default for RigidBody {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A body the solver can simulate. Shapes only provide their geometry and
+/// inertia; everything else goes through the `RigidBody` they hold.
+pub trait PhysicsObject {
+    fn body(&self) -> &RigidBody;
+    fn body_mut(&mut self) -> &mut RigidBody;
+    fn shape(&self) -> Shape<'_>;
+    /// `1 / I` about the centre of mass, zero for static and kinematic
+    /// bodies.
+    fn get_inverse_inertia(&self) -> Real;
+
+    fn body_type(&self) -> BodyType {
+        self.body().body_type
+    }
+    /// `1 / m`, or zero for static and kinematic bodies. The solver only
+    /// ever uses this, so infinite mass needs no special case.
+    fn get_inverse_mass(&self) -> Real {
+        match self.body_type() {
+            BodyType::Dynamic => self.body().inverse_mass,
+            BodyType::Static | BodyType::Kinematic => 0.0,
+        }
+    }
+    fn get_mass(&self) -> Real {
+        1.0 / self.get_inverse_mass()
+    }
+    /// Velocity in world units per second.
+    fn get_velocity(&self) -> Vector2 {
+        self.body().velocity
+    }
+    fn get_acceleration(&self) -> Vector2 {
+        self.body().acceleration
+    }
+    fn get_position(&self) -> Vector2 {
+        self.body().position
+    }
+    /// Rotation in radians. With `y` pointing down, positive angles turn
+    /// clockwise on screen.
+    fn get_angle(&self) -> Real {
+        self.body().angle
+    }
+    /// In radians per second.
+    fn get_angular_velocity(&self) -> Real {
+        self.body().angular_velocity
+    }
+    fn get_angular_acceleration(&self) -> Real {
+        self.body().angular_acceleration
+    }
+    fn get_friction(&self) -> Friction {
+        self.body().friction
+    }
+    /// Coefficient of restitution `e`, the fraction of the closing speed a
+    /// contact separates at: 0 does not bounce, 1 bounces elastically.
+    fn get_restitution(&self) -> Real {
+        self.body().restitution
+    }
+
+    fn get_velocity_mut(&mut self) -> &mut Vector2 {
+        &mut self.body_mut().velocity
+    }
+    fn get_acceleration_mut(&mut self) -> &mut Vector2 {
+        &mut self.body_mut().acceleration
+    }
+    fn get_position_mut(&mut self) -> &mut Vector2 {
+        &mut self.body_mut().position
+    }
+
+    fn set_body_type(&mut self, body_type: BodyType) {
+        let body = self.body_mut();
+        body.body_type = body_type;
+        if body_type == BodyType::Static {
+            body.velocity = Vector2::zero();
+            body.angular_velocity = 0.0;
+        }
+    }
+    /// Sets the mass the body has while dynamic. Infinite mass makes the
+    /// body static instead; zero, negative and NaN masses panic.
+    fn set_mass(&mut self, mass: Real) {
+        assert!(mass > 0.0, "A body's mass must be positive, not {mass}");
+        if mass.is_infinite() {
+            self.set_body_type(BodyType::Static);
+        } else {
+            self.body_mut().inverse_mass = 1.0 / mass;
+        }
+    }
+    fn set_velocity(&mut self, velocity: Vector2) {
+        self.body_mut().velocity = velocity;
+    }
+    fn set_acceleration(&mut self, acceleration: Vector2) {
+        self.body_mut().acceleration = acceleration;
+    }
+    fn set_position(&mut self, position: Vector2) {
+        self.body_mut().position = position;
+    }
+    fn set_angle(&mut self, angle: Real) {
+        self.body_mut().angle = angle;
+    }
+    fn set_angular_velocity(&mut self, angular_velocity: Real) {
+        self.body_mut().angular_velocity = angular_velocity;
+    }
+    fn set_angular_acceleration(&mut self, angular_acceleration: Real) {
+        self.body_mut().angular_acceleration = angular_acceleration;
+    }
+    fn set_friction(&mut self, friction: Friction) {
+        self.body_mut().friction = friction;
+    }
+    fn set_restitution(&mut self, restitution: Real) {
+        self.body_mut().restitution = restitution;
+    }
+
+    fn accelerate(&mut self, acc: Vector2) {
+        self.body_mut().acceleration += acc;
+    }
+    fn accelerate_angular(&mut self, acc: Real) {
+        self.body_mut().angular_acceleration += acc;
+    }
+
+    /// Rotates the body-space vector `local` into world space.
+    fn to_world(&self, local: Vector2) -> Vector2 {
+        local.rotated(self.get_angle())
+    }
+}
+
+#[derive(Debug)]
+pub struct Circle {
+    pub radius: Real,
+    pub body: RigidBody,
+}
+
+impl Circle {
+    pub fn new() -> Self {
+        Self {
+            radius: 0.05,
+            body: RigidBody::new(),
+        }
+    }
+}
+
+impl Default for Circle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PhysicsObject for Circle {
+    fn body(&self) -> &RigidBody {
+        &self.body
+    }
+    fn body_mut(&mut self) -> &mut RigidBody {
+        &mut self.body
+    }
+    fn shape(&self) -> Shape<'_> {
+        Shape::Circle {
+            radius: self.radius,
+        }
+    }
+    /// A uniform disc, `I = ½ m r²`.
+    fn get_inverse_inertia(&self) -> Real {
+        2.0 * self.get_inverse_mass() / (self.radius * self.radius)
+    }
+}
+
+/// A convex polygon of uniform density.
+#[derive(Debug)]
+pub struct Polygon {
+    /// Body-space vertices, centred on the centre of mass. See
+    /// `Shape::Polygon` for the winding.
+    vertices: Vec<Vector2>,
+    /// Moment of inertia per unit mass, `I / m`.
+    inertia_per_mass: Real,
+    /// Keeps the body at its current angle, like an axis-aligned box.
+    pub fixed_rotation: bool,
+    pub body: RigidBody,
+}
+
+impl Polygon {
+    /// A polygon with the given outline, which must be convex. The vertices
+    /// are moved so the centroid is at the origin, and may be given in
+    /// either winding.
+    pub fn new(mut vertices: Vec<Vector2>) -> Self {
+        assert!(
+            vertices.len() >= 3,
+            "A polygon needs at least three vertices"
+        );
+
+        // Signed area and centroid from the triangles fanned out from the
+        // origin.
+        let mut twice_area = 0.0;
+        let mut centroid = Vector2::zero();
+        for (index, &vertex) in vertices.iter().enumerate() {
+            let next = vertices[(index + 1) % vertices.len()];
+            let cross = vertex.cross(next);
+            twice_area += cross;
+            centroid += (vertex + next) * cross;
+        }
+        centroid /= 3.0 * twice_area;
+        if twice_area < 0.0 {
+            vertices.reverse();
+        }
+        for vertex in vertices.iter_mut() {
+            *vertex -= centroid;
+        }
+
+        // `I / m = Σ cross (a·a + a·b + b·b) / (6 Σ cross)` over the edges.
+        let mut numerator = 0.0;
+        let mut denominator = 0.0;
+        for (index, &vertex) in vertices.iter().enumerate() {
+            let next = vertices[(index + 1) % vertices.len()];
+            let cross = vertex.cross(next);
+            numerator += cross * (vertex.dot(vertex) + vertex.dot(next) + next.dot(next));
+            denominator += cross;
+        }
+
+        Self {
+            vertices,
+            inertia_per_mass: numerator / (6.0 * denominator),
+            fixed_rotation: false,
+            body: RigidBody::new(),
+        }
+    }
+
+    /// A `width` by `height` box that is free to rotate.
+    pub fn rectangle(width: Real, height: Real) -> Self {
+        let (x, y) = (0.5 * width, 0.5 * height);
+        Self::new(vec![
+            Vector2::new(-x, -y),
+            Vector2::new(x, -y),
+            Vector2::new(x, y),
+            Vector2::new(-x, y),
+        ])
+    }
+
+    /// A `width` by `height` box that never rotates, so stays aligned with
+    /// the axes.
+    pub fn axis_aligned_box(width: Real, height: Real) -> Self {
+        Self {
+            fixed_rotation: true,
+            ..Self::rectangle(width, height)
+        }
+    }
+
+    /// A regular polygon with `sides` vertices at `radius` from the centre.
+    pub fn regular(sides: usize, radius: Real) -> Self {
+        Self::new(
+            (0..sides)
+                .map(|index| {
+                    let angle = index as Real / sides as Real * 2.0 * std::f64::consts::PI as Real;
+                    Vector2::new(radius * angle.cos(), radius * angle.sin())
+                })
+                .collect(),
+        )
+    }
+
+    pub fn vertices(&self) -> &[Vector2] {
+        &self.vertices
+    }
+}
+
+impl PhysicsObject for Polygon {
+    fn body(&self) -> &RigidBody {
+        &self.body
+    }
+    fn body_mut(&mut self) -> &mut RigidBody {
+        &mut self.body
+    }
+    fn shape(&self) -> Shape<'_> {
+        Shape::Polygon {
+            vertices: &self.vertices,
+        }
+    }
+    fn get_inverse_inertia(&self) -> Real {
+        if self.fixed_rotation {
+            0.0
+        } else {
+            self.get_inverse_mass() / self.inertia_per_mass
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rectangle_has_the_inertia_of_a_uniform_box() {
+        let (width, height) = (0.4, 0.2);
+        let rectangle = Polygon::rectangle(width, height);
+        let expected = (width * width + height * height) / 12.0;
+        assert!(
+            (rectangle.inertia_per_mass - expected).abs() < 1e-6,
+            "I/m {} vs {expected}",
+            rectangle.inertia_per_mass
+        );
+    }
+
+    #[test]
+    fn clockwise_outline_is_rewound_and_recentred() {
+        // A 3 by 2 box centred on (2.5, 2), listed the other way round from
+        // `Polygon::rectangle`.
+        let polygon = Polygon::new(vec![
+            Vector2::new(1.0, 1.0),
+            Vector2::new(1.0, 3.0),
+            Vector2::new(4.0, 3.0),
+            Vector2::new(4.0, 1.0),
+        ]);
+        let expected = [
+            Vector2::new(1.5, -1.0),
+            Vector2::new(1.5, 1.0),
+            Vector2::new(-1.5, 1.0),
+            Vector2::new(-1.5, -1.0),
+        ];
+        for (&vertex, &expected) in polygon.vertices().iter().zip(&expected) {
+            assert!((vertex - expected).length() < 1e-6, "{vertex:?}");
+        }
+        assert!(
+            (polygon.inertia_per_mass - Polygon::rectangle(3.0, 2.0).inertia_per_mass).abs() < 1e-5
+        );
+    }
+}
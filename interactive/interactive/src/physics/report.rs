@@ -0,0 +1,61 @@
+use crate::physics::math::Real;
+use std::fmt;
+use std::time::Duration;
+
+/// Wall-clock time spent in each phase of a step, summed over substeps.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PhaseTimings {
+    /// Applied forces and the position-level mouse constraint.
+    pub forces: Duration,
+    /// Building `J`, the bounds and the right-hand side.
+    pub assembly: Duration,
+    /// The λ solve and applying `Jᵀλ`.
+    pub solve: Duration,
+    pub integration: Duration,
+}
+
+impl PhaseTimings {
+    pub fn total(&self) -> Duration {
+        self.forces + self.assembly + self.solve + self.integration
+    }
+}
+
+/// What happened during one call to `Solver::step`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StepReport {
+    pub substeps: u32,
+    /// Constraint rows solved, summed over substeps.
+    pub rows: usize,
+    /// Rows that started from a cached λ.
+    pub warm_rows: usize,
+    /// Linear solver iterations, summed over substeps.
+    pub iterations: usize,
+    /// Whether the linear solver converged in every substep.
+    pub converged: bool,
+    /// Largest final residual `|right - [J W Jᵀ]λ|` of any substep.
+    pub residual: Real,
+    /// Largest `|C|` seen before any solve.
+    pub max_constraint_error: Real,
+    /// Mean `|C|` over every row solved.
+    pub mean_constraint_error: Real,
+    pub max_lambda: Real,
+    pub timings: PhaseTimings,
+}
+
+impl fmt::Display for StepReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} rows ({} warm), {} iterations{}, residual {:.3e}, |C| max {:.3} mean {:.3}, max |λ| {:.3e}, {:.2} ms",
+            self.rows,
+            self.warm_rows,
+            self.iterations,
+            if self.converged { "" } else { " (not converged)" },
+            self.residual,
+            self.max_constraint_error,
+            self.mean_constraint_error,
+            self.max_lambda,
+            self.timings.total().as_secs_f64() * 1000_f64,
+        )
+    }
+}
@@ -0,0 +1,397 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+use ndarray::Array1;
+
+use crate::physics::constraints::*;
+use crate::physics::forces::{ForceGenerator, LinearDrag, UniformGravity};
+use crate::physics::input::{InputState, PointerButton};
+use crate::physics::integrators::{BodyAcceleration, BodyState, Integrator, Verlet};
+use crate::physics::linear::{GaussSeidel, LambdaBounds, LinearSolver};
+use crate::physics::math::{Real, Vector2};
+use crate::physics::objects::*;
+use crate::physics::report::StepReport;
+use crate::physics::sparse::{SparseJacobian, SystemMatrix};
+
+/// Key of a cached λ: the constraint's index in `Solver::constraints` plus
+/// the row's identity within it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct RowKey {
+    constraint: usize,
+    row: RowId,
+}
+
+pub struct Solver {
+    pub scene_objects: Vec<Box<dyn PhysicsObject>>,
+    pub constraints: Vec<Box<dyn Constraint>>,
+    /// Applied forces, summed into `Q` before every constraint solve.
+    pub force_generators: Vec<Box<dyn ForceGenerator>>,
+
+    pub linear_solver: Box<dyn LinearSolver>,
+    pub integrator: Box<dyn Integrator>,
+    /// Start each λ solve from the previous substep's λ for rows that
+    /// persist, instead of from zero.
+    pub warm_starting: bool,
+
+    warm_start_cache: HashMap<RowKey, Real>,
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Self {
+            scene_objects: Vec::with_capacity(100),
+            constraints: Vec::with_capacity(100),
+            force_generators: vec![
+                Box::new(UniformGravity::default()),
+                Box::new(LinearDrag::new(0.6)),
+            ],
+            linear_solver: Box::new(GaussSeidel::new()),
+            integrator: Box::new(Verlet),
+            warm_starting: true,
+            warm_start_cache: HashMap::new(),
+        }
+    }
+
+    /// Advances the scene by `dt` seconds, split into `sub_steps` substeps.
+    pub fn step(&mut self, dt: Real, sub_steps: u32, input: &InputState) -> StepReport {
+        let sub_dt: Real = dt / sub_steps as Real;
+        let mut report = StepReport {
+            substeps: sub_steps,
+            converged: true,
+            ..Default::default()
+        };
+        for _i in 0..sub_steps {
+            let phase_start = Instant::now();
+            self.apply_constraints(input, sub_dt);
+            report.timings.forces += phase_start.elapsed();
+
+            self.integrate(input, sub_dt, &mut report);
+        }
+        // solve_constraints accumulates the sum of |C|; turn it into the mean.
+        if report.rows > 0 {
+            report.mean_constraint_error /= report.rows as Real;
+        }
+        report
+    }
+
+    /// Sets every body's linear and angular acceleration from the sum of the
+    /// applied forces and torques on it. Static and kinematic bodies are not
+    /// accelerated.
+    pub fn apply_forces(&mut self) {
+        let mut forces = vec![Vector2::zero(); self.scene_objects.len()];
+        let mut torques = vec![0.0; self.scene_objects.len()];
+        for generator in self.force_generators.iter_mut() {
+            generator.apply(&self.scene_objects, &mut forces, &mut torques);
+        }
+        for ((ele, force), torque) in self.scene_objects.iter_mut().zip(forces).zip(torques) {
+            match ele.body_type() {
+                BodyType::Dynamic => {
+                    let acceleration = force * ele.get_inverse_mass();
+                    let angular_acceleration = torque * ele.get_inverse_inertia();
+                    ele.set_acceleration(acceleration);
+                    ele.set_angular_acceleration(angular_acceleration);
+                }
+                BodyType::Static | BodyType::Kinematic => {
+                    ele.set_acceleration(Vector2::zero());
+                    ele.set_angular_acceleration(0.0);
+                }
+            }
+        }
+    }
+
+    #[allow(non_snake_case)]
+    pub fn solve_constraints(&mut self, input: &InputState, dt: Real, report: &mut StepReport) {
+        let assembly_start = Instant::now();
+        let dof = self.scene_objects.len() * DOF_PER_BODY;
+
+        // Generalised velocity q̇, `[vx, vy, ω]` per body.
+        let q_dot = Array1::<Real>::from_shape_fn(dof, |i| {
+            let ele = &self.scene_objects[i / DOF_PER_BODY];
+            match i % DOF_PER_BODY {
+                0 => ele.get_velocity().x,
+                1 => ele.get_velocity().y,
+                _ => ele.get_angular_velocity(),
+            }
+        });
+
+        // Stack each constraint's rows into J, alongside the bounds on their λ,
+        // J̇q̇, and the Baumgarte bias that pulls drifted rows back to C = 0
+        // and their Ċ towards its target.
+        let mut J = SparseJacobian::new(dof);
+        let mut bound_pairs = Vec::new();
+        let mut scale_rows = Vec::new();
+        let mut J_dot_q_dot = Vec::new();
+        let mut bias = Vec::new();
+        let mut row_keys = Vec::new();
+        for (constraint_index, constraint) in self.constraints.iter_mut().enumerate() {
+            let baumgarte = constraint.baumgarte();
+            let row_start = J.rows();
+            for row in constraint.rows(&self.scene_objects, input) {
+                J.push_row(&row.entries);
+                bound_pairs.push(row.bounds);
+                scale_rows.push(row.scale_row.map(|scale_row| row_start + scale_row));
+                J_dot_q_dot.push(row.j_dot_q_dot);
+                row_keys.push(RowKey {
+                    constraint: constraint_index,
+                    row: row.id,
+                });
+
+                report.max_constraint_error = report.max_constraint_error.max(row.c.abs());
+                report.mean_constraint_error += row.c.abs();
+                let velocity_error: Real = row
+                    .entries
+                    .iter()
+                    .map(|&(column, value)| value * q_dot[column])
+                    .sum();
+                // A bouncing row goes straight to its target velocity, with
+                // no position term, which would add energy to the bounce.
+                if row.target > 0.0 {
+                    bias.push((velocity_error - row.target) / dt);
+                } else {
+                    bias.push(
+                        baumgarte.beta / (dt * dt) * row.c
+                            + baumgarte.damping / dt * velocity_error,
+                    );
+                }
+            }
+        }
+        let J_dot_q_dot = Array1::from_vec(J_dot_q_dot);
+        let bias = Array1::from_vec(bias);
+        let bounds = LambdaBounds::with_scale_rows(&bound_pairs, scale_rows);
+        let row_total = J.rows();
+
+        let W = Array1::<Real>::from_shape_fn(dof, |i| {
+            let ele = &self.scene_objects[i / DOF_PER_BODY];
+            match i % DOF_PER_BODY {
+                0 | 1 => ele.get_inverse_mass(),
+                _ => ele.get_inverse_inertia(),
+            }
+        });
+
+        // W Q is the acceleration due to applied forces alone, which is what
+        // `apply_forces` left on each body. Q itself is never formed, so
+        // infinite-mass bodies stay out of the arithmetic.
+        let W_Q = Array1::<Real>::from_shape_fn(dof, |i| {
+            let ele = &self.scene_objects[i / DOF_PER_BODY];
+            match i % DOF_PER_BODY {
+                0 => ele.get_acceleration().x,
+                1 => ele.get_acceleration().y,
+                _ => ele.get_angular_acceleration(),
+            }
+        });
+
+        let mut warm_rows = 0;
+        let initial = Array1::<Real>::from_shape_fn(row_total, |row| {
+            if !self.warm_starting {
+                return 0.0;
+            }
+            match self.warm_start_cache.get(&row_keys[row]) {
+                Some(&lambda) => {
+                    warm_rows += 1;
+                    lambda
+                }
+                None => 0.0,
+            }
+        });
+
+        let right: Array1<Real> = -J_dot_q_dot - J.mul_vec(&W_Q) - bias;
+        let left = SystemMatrix {
+            jacobian: &J,
+            inverse_mass: &W,
+        };
+
+        report.timings.assembly += assembly_start.elapsed();
+        if row_total == 0 {
+            self.warm_start_cache.clear();
+            return;
+        }
+
+        let solve_start = Instant::now();
+        let solution = self.linear_solver.solve(&left, &right, &bounds, &initial);
+        let lambda = solution.x;
+        let constraint_forces = J.transpose_mul_vec(&lambda);
+
+        report.rows += row_total;
+        report.warm_rows += warm_rows;
+        report.iterations += solution.iterations;
+        report.converged &= solution.converged;
+        report.residual = report.residual.max(solution.residual);
+        report.max_lambda = lambda
+            .iter()
+            .fold(report.max_lambda, |largest, value| largest.max(value.abs()));
+        // Rows that were not solved this substep have separated, so their
+        // cached λ is dropped rather than revived later.
+        self.warm_start_cache = row_keys.into_iter().zip(lambda.iter().copied()).collect();
+
+        // Apply calculated forces. Velocity into a constraint is removed by
+        // the damping part of the bias, so the integrator takes it from here.
+        // Only dynamic bodies respond, as in `apply_forces`.
+        for (index, ele) in self.scene_objects.iter_mut().enumerate() {
+            if ele.body_type() != BodyType::Dynamic {
+                continue;
+            }
+            let column = index * DOF_PER_BODY;
+            let force = Vector2::new(constraint_forces[column], constraint_forces[column + 1]);
+            let torque = constraint_forces[column + 2];
+            let inverse_mass = ele.get_inverse_mass();
+            let inverse_inertia = ele.get_inverse_inertia();
+            ele.accelerate(force * inverse_mass);
+            ele.accelerate_angular(torque * inverse_inertia);
+        }
+        report.timings.solve += solve_start.elapsed();
+    }
+
+    /// Projects bodies back inside the drag circle. Half the correction is
+    /// kept as velocity so dragged bodies carry some momentum when released.
+    pub fn apply_constraints(&mut self, input: &InputState, dt: Real) {
+        if let Some(mouse_position) = input.drag_position(PointerButton::Left) {
+            let centre_position = mouse_position;
+            let centre_radius = 1.0;
+
+            for obj in self.scene_objects.iter_mut() {
+                if obj.body_type() != BodyType::Dynamic {
+                    continue;
+                }
+                let to_obj: Vector2 = obj.get_position() - centre_position;
+                let dist: Real = (to_obj.x.powf(2.0) + to_obj.y.powf(2.0)).sqrt();
+
+                if dist > centre_radius {
+                    let original_position = obj.get_position();
+                    let n: Vector2 = to_obj / dist;
+                    obj.set_position(centre_position + n * centre_radius);
+                    let position_offset = obj.get_position() - original_position;
+                    obj.set_velocity(obj.get_velocity() + position_offset / (2.0 * dt));
+                }
+            }
+        }
+    }
+
+    /// Advances every body by one substep with `self.integrator`. Each
+    /// acceleration evaluation the integrator asks for applies the force
+    /// generators and solves the constraints at that trial state.
+    fn integrate(&mut self, input: &InputState, dt: Real, report: &mut StepReport) {
+        let integration_start = Instant::now();
+        for constraint in self.constraints.iter_mut() {
+            constraint.begin_substep(&self.scene_objects);
+        }
+        let state = self.body_states();
+        // Evaluations report their own phases, so their time is taken back
+        // out of the integration phase.
+        let evaluation_start = Instant::now();
+        let acceleration = self.evaluate_accelerations(&state, input, dt, report);
+        let mut evaluation_time = evaluation_start.elapsed();
+
+        // The integrator is taken out for the call so the evaluation closure
+        // can borrow the rest of the solver.
+        let mut integrator = std::mem::replace(&mut self.integrator, Box::new(Verlet));
+        let new_state = integrator.integrate(&state, &acceleration, dt, &mut |trial| {
+            let evaluation_start = Instant::now();
+            let acceleration = self.evaluate_accelerations(trial, input, dt, report);
+            evaluation_time += evaluation_start.elapsed();
+            acceleration
+        });
+        self.integrator = integrator;
+
+        self.set_body_states(&new_state);
+        for ele in self.scene_objects.iter_mut() {
+            ele.set_acceleration(Vector2::zero());
+            ele.set_angular_acceleration(0.0);
+        }
+        report.timings.integration += integration_start.elapsed() - evaluation_time;
+    }
+
+    fn body_states(&self) -> Vec<BodyState> {
+        self.scene_objects
+            .iter()
+            .map(|ele| BodyState {
+                position: ele.get_position(),
+                velocity: ele.get_velocity(),
+                angle: ele.get_angle(),
+                angular_velocity: ele.get_angular_velocity(),
+            })
+            .collect()
+    }
+
+    fn set_body_states(&mut self, state: &[BodyState]) {
+        for (ele, body) in self.scene_objects.iter_mut().zip(state) {
+            ele.set_position(body.position);
+            ele.set_velocity(body.velocity);
+            ele.set_angle(body.angle);
+            ele.set_angular_velocity(body.angular_velocity);
+        }
+    }
+
+    /// Moves the scene to `state` and returns each body's acceleration there,
+    /// applied forces plus constraint forces.
+    fn evaluate_accelerations(
+        &mut self,
+        state: &[BodyState],
+        input: &InputState,
+        dt: Real,
+        report: &mut StepReport,
+    ) -> Vec<BodyAcceleration> {
+        let phase_start = Instant::now();
+        self.set_body_states(state);
+        self.apply_forces();
+        report.timings.forces += phase_start.elapsed();
+
+        self.solve_constraints(input, dt, report);
+
+        self.scene_objects
+            .iter()
+            .map(|ele| BodyAcceleration {
+                linear: ele.get_acceleration(),
+                angular: ele.get_angular_acceleration(),
+            })
+            .collect()
+    }
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::contacts::BodyContacts;
+
+    /// Three 0.4 m boxes stacked on the floor, left to settle, then stepped
+    /// once more.
+    fn settled_stack_step(warm_starting: bool) -> StepReport {
+        let mut solver = Solver::new();
+        solver.warm_starting = warm_starting;
+        for level in 0..3 {
+            let mut block = Polygon::rectangle(0.4, 0.4);
+            block.set_position(Vector2::new(3.2, 4.6 - 0.4 * level as Real));
+            solver.scene_objects.push(Box::new(block));
+        }
+        solver.constraints.push(Box::new(ScreenEdge::new()));
+        solver.constraints.push(Box::new(BodyContacts::new()));
+
+        let input = InputState::new();
+        for _ in 0..120 {
+            solver.step(1.0 / 60.0, 10, &input);
+        }
+        solver.step(1.0 / 60.0, 10, &input)
+    }
+
+    #[test]
+    fn resting_contacts_start_warm() {
+        let warm = settled_stack_step(true);
+        let cold = settled_stack_step(false);
+
+        // Resting contacts keep their rows from one substep to the next, so
+        // nearly all of them find a cached λ.
+        assert!(warm.rows > 0);
+        assert!(
+            warm.warm_rows * 10 >= warm.rows * 9,
+            "{} of {} rows warm",
+            warm.warm_rows,
+            warm.rows
+        );
+        assert_eq!(cold.warm_rows, 0);
+    }
+}
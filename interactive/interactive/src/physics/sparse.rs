@@ -0,0 +1,103 @@
+use crate::physics::math::Real;
+use ndarray::Array1;
+
+/// Constraint Jacobian `J` in compressed sparse row form.
+///
+/// Most rows only touch the handful of coordinates belonging to the bodies
+/// involved, so storing `J` densely wastes memory and time as the scene
+/// grows. Rows are appended in order with `push_row`.
+#[derive(Debug, Clone, Default)]
+pub struct SparseJacobian {
+    columns: usize,
+    row_offsets: Vec<usize>,
+    column_indices: Vec<usize>,
+    values: Vec<Real>,
+}
+
+impl SparseJacobian {
+    pub fn new(columns: usize) -> Self {
+        Self {
+            columns,
+            row_offsets: vec![0],
+            column_indices: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    /// Appends a row given as `(column, value)` pairs. Zero entries are
+    /// skipped.
+    pub fn push_row(&mut self, entries: &[(usize, Real)]) {
+        for &(column, value) in entries {
+            assert!(column < self.columns, "Jacobian column out of range");
+            if value != 0.0 {
+                self.column_indices.push(column);
+                self.values.push(value);
+            }
+        }
+        self.row_offsets.push(self.values.len());
+    }
+
+    pub fn rows(&self) -> usize {
+        self.row_offsets.len() - 1
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Non-zero `(column, value)` pairs of `row`.
+    pub fn row(&self, row: usize) -> impl Iterator<Item = (usize, Real)> + '_ {
+        let range = self.row_offsets[row]..self.row_offsets[row + 1];
+        self.column_indices[range.clone()]
+            .iter()
+            .copied()
+            .zip(self.values[range].iter().copied())
+    }
+
+    /// `J x`
+    pub fn mul_vec(&self, x: &Array1<Real>) -> Array1<Real> {
+        Array1::from_shape_fn(self.rows(), |row| {
+            self.row(row).map(|(column, value)| value * x[column]).sum()
+        })
+    }
+
+    /// `Jᵀ y`
+    pub fn transpose_mul_vec(&self, y: &Array1<Real>) -> Array1<Real> {
+        let mut output = Array1::<Real>::zeros(self.columns);
+        for row in 0..self.rows() {
+            for (column, value) in self.row(row) {
+                output[column] += value * y[row];
+            }
+        }
+        output
+    }
+}
+
+/// The matrix `J W Jᵀ` of the λ system, applied implicitly. `W` is the
+/// diagonal inverse mass matrix stored as a vector.
+pub struct SystemMatrix<'a> {
+    pub jacobian: &'a SparseJacobian,
+    pub inverse_mass: &'a Array1<Real>,
+}
+
+impl SystemMatrix<'_> {
+    pub fn size(&self) -> usize {
+        self.jacobian.rows()
+    }
+
+    /// `J W Jᵀ x` without forming the matrix.
+    pub fn apply(&self, x: &Array1<Real>) -> Array1<Real> {
+        let impulse = self.jacobian.transpose_mul_vec(x) * self.inverse_mass;
+        self.jacobian.mul_vec(&impulse)
+    }
+
+    /// Diagonal of `J W Jᵀ`.
+    pub fn diagonal(&self) -> Array1<Real> {
+        Array1::from_shape_fn(self.size(), |row| {
+            self.jacobian
+                .row(row)
+                .map(|(column, value)| value * value * self.inverse_mass[column])
+                .sum()
+        })
+    }
+}
@@ -0,0 +1,177 @@
+use crate::physics::input::InputState;
+use crate::physics::math::{Real, Vector2};
+use crate::physics::report::StepReport;
+use crate::physics::solver::Solver;
+
+/// Fixed-step driver for a `Solver`.
+///
+/// Frame time is banked in an accumulator and spent in whole steps of `step`
+/// seconds, so the simulation advances at the same rate whatever the frame
+/// rate is. The leftover fraction of a step is used to interpolate rendered
+/// positions and angles between the last two physics states.
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    /// Length of one physics step in seconds.
+    pub step: Real,
+    pub sub_steps: u32,
+    /// Most steps taken in one frame. Time beyond that is dropped, so a long
+    /// stall slows the simulation down instead of freezing the frontend while
+    /// it catches up.
+    pub max_steps_per_frame: u32,
+
+    accumulator: Real,
+    previous_positions: Vec<Vector2>,
+    previous_angles: Vec<Real>,
+}
+
+impl FixedTimestep {
+    pub fn new(step: Real, sub_steps: u32) -> Self {
+        Self {
+            step,
+            sub_steps,
+            max_steps_per_frame: 5,
+            accumulator: 0.0,
+            previous_positions: Vec::new(),
+            previous_angles: Vec::new(),
+        }
+    }
+
+    /// Banks `frame_time` seconds and takes as many whole steps as it pays
+    /// for. Returns the report of the last step taken, if any.
+    pub fn advance(
+        &mut self,
+        solver: &mut Solver,
+        frame_time: Real,
+        input: &InputState,
+    ) -> Option<StepReport> {
+        self.accumulator += frame_time.max(0.0);
+
+        let mut report = None;
+        let mut steps = 0;
+        while self.accumulator >= self.step {
+            if steps == self.max_steps_per_frame {
+                self.accumulator %= self.step;
+                break;
+            }
+            self.previous_positions = solver
+                .scene_objects
+                .iter()
+                .map(|ele| ele.get_position())
+                .collect();
+            self.previous_angles = solver
+                .scene_objects
+                .iter()
+                .map(|ele| ele.get_angle())
+                .collect();
+            report = Some(solver.step(self.step, self.sub_steps, input));
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+        report
+    }
+
+    /// How far the current time is between the last two physics states, in
+    /// `[0, 1)`.
+    pub fn alpha(&self) -> Real {
+        (self.accumulator / self.step).clamp(0.0, 1.0)
+    }
+
+    /// Position of body `index` blended between the last two physics states.
+    /// Bodies added since the last step are drawn where they are.
+    pub fn interpolated_position(&self, solver: &Solver, index: usize) -> Vector2 {
+        let current = solver.scene_objects[index].get_position();
+        match self.previous_positions.get(index) {
+            Some(&previous) => previous + (current - previous) * self.alpha(),
+            None => current,
+        }
+    }
+
+    /// Angle of body `index` blended between the last two physics states.
+    /// Angles are not wrapped, so this turns the short way for any spin
+    /// slower than half a turn per step.
+    pub fn interpolated_angle(&self, solver: &Solver, index: usize) -> Real {
+        let current = solver.scene_objects[index].get_angle();
+        match self.previous_angles.get(index) {
+            Some(&previous) => previous + (current - previous) * self.alpha(),
+            None => current,
+        }
+    }
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        Self::new(1.0 / 60.0, 10)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::objects::{Circle, PhysicsObject};
+
+    /// Quarter-second steps, which floats hold exactly.
+    const STEP: Real = 0.25;
+
+    /// A circle coasting at 1 m/s with nothing acting on it, so the distance
+    /// it has covered counts the steps taken.
+    fn coasting_circle() -> Solver {
+        let mut solver = Solver::new();
+        solver.force_generators.clear();
+        let mut circle = Circle::new();
+        circle.set_position(Vector2::zero());
+        circle.set_velocity(Vector2::new(1.0, 0.0));
+        solver.scene_objects.push(Box::new(circle));
+        solver
+    }
+
+    fn steps_taken(solver: &Solver) -> Real {
+        solver.scene_objects[0].get_position().x / STEP
+    }
+
+    fn assert_close(actual: Real, expected: Real) {
+        assert!((actual - expected).abs() < 1e-4, "{actual} vs {expected}");
+    }
+
+    #[test]
+    fn frame_time_is_spent_in_whole_steps() {
+        let mut solver = coasting_circle();
+        let mut timestep = FixedTimestep::new(STEP, 1);
+        let input = InputState::new();
+
+        assert!(timestep.advance(&mut solver, 0.1, &input).is_none());
+        assert_close(steps_taken(&solver), 0.0);
+        assert_close(timestep.alpha(), 0.4);
+
+        assert!(timestep.advance(&mut solver, 0.2, &input).is_some());
+        assert_close(steps_taken(&solver), 1.0);
+        assert_close(timestep.alpha(), 0.2);
+
+        timestep.advance(&mut solver, 0.6, &input);
+        assert_close(steps_taken(&solver), 3.0);
+        assert_close(timestep.alpha(), 0.6);
+    }
+
+    #[test]
+    fn long_frames_take_at_most_max_steps() {
+        let mut solver = coasting_circle();
+        let mut timestep = FixedTimestep::new(STEP, 1);
+        let input = InputState::new();
+
+        // Ten and a bit steps' worth: five are taken, the rest is dropped but
+        // for the part of a step left over.
+        timestep.advance(&mut solver, 2.6, &input);
+        assert_close(steps_taken(&solver), timestep.max_steps_per_frame as Real);
+        assert_close(timestep.alpha(), 0.4);
+    }
+
+    #[test]
+    fn positions_are_interpolated_by_alpha() {
+        let mut solver = coasting_circle();
+        let mut timestep = FixedTimestep::new(STEP, 1);
+        let input = InputState::new();
+
+        // One step from 0 to 0.25 m, and 0.1 s towards the next.
+        timestep.advance(&mut solver, 0.35, &input);
+        assert_close(timestep.interpolated_position(&solver, 0).x, 0.1);
+    }
+}
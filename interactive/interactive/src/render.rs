@@ -0,0 +1,134 @@
+use interactive::physics::{self, Aabb, BodyType, PhysicsObject, Real, Shape, StepReport};
+use raylib::prelude::*;
+
+use crate::camera::Camera;
+
+/// Draws `object` at the world-space `position` and `angle`, which may be
+/// interpolated rather than the body's current pose.
+pub fn draw_object(
+    d: &mut RaylibDrawHandle,
+    camera: &Camera,
+    object: &dyn PhysicsObject,
+    position: physics::Vector2,
+    angle: Real,
+) {
+    let color = match object.body_type() {
+        BodyType::Dynamic => Color::RED,
+        BodyType::Static => Color::DARKGRAY,
+        BodyType::Kinematic => Color::BLUE,
+    };
+    match object.shape() {
+        Shape::Circle { radius } => {
+            d.draw_circle_v(
+                Vector2::from(camera.world_to_screen(position)),
+                camera.scale(radius) as f32,
+                color,
+            );
+            // A spoke from the centre so rotation is visible.
+            let rim = position + physics::Vector2::new(radius, 0.0).rotated(angle);
+            d.draw_line_v(
+                Vector2::from(camera.world_to_screen(position)),
+                Vector2::from(camera.world_to_screen(rim)),
+                Color::WHITE,
+            );
+        }
+        Shape::Polygon { vertices } => {
+            let points: Vec<Vector2> = vertices
+                .iter()
+                .map(|&vertex| {
+                    Vector2::from(camera.world_to_screen(position + vertex.rotated(angle)))
+                })
+                .collect();
+            // Raylib fills fans wound anticlockwise on screen, the reverse
+            // of the order polygons keep their vertices in.
+            let fan: Vec<Vector2> = points.iter().rev().copied().collect();
+            d.draw_triangle_fan(&fan, color);
+            // Closed outline, so rotation stays visible on regular shapes.
+            let mut outline = points;
+            outline.push(outline[0]);
+            d.draw_line_strip(&outline, Color::WHITE);
+        }
+    }
+}
+
+/// A line between the centres of two linked bodies, drawn at `a` and `b`.
+pub fn draw_link(
+    d: &mut RaylibDrawHandle,
+    camera: &Camera,
+    a: physics::Vector2,
+    b: physics::Vector2,
+) {
+    d.draw_line_v(
+        Vector2::from(camera.world_to_screen(a)),
+        Vector2::from(camera.world_to_screen(b)),
+        Color::BLACK,
+    );
+}
+
+/// Outlines the broadphase's grid `cells` and joins the centres of each
+/// candidate pair, with bodies drawn at `positions`.
+pub fn draw_broadphase(
+    d: &mut RaylibDrawHandle,
+    camera: &Camera,
+    cells: &[Aabb],
+    pairs: &[(usize, usize)],
+    positions: &[physics::Vector2],
+) {
+    for cell in cells {
+        let min = camera.world_to_screen(cell.min);
+        let max = camera.world_to_screen(cell.max);
+        d.draw_rectangle_lines_ex(
+            Rectangle::new(
+                min.x as f32,
+                min.y as f32,
+                (max.x - min.x) as f32,
+                (max.y - min.y) as f32,
+            ),
+            1_f32,
+            Color::LIGHTGRAY,
+        );
+    }
+    for &(body_a, body_b) in pairs {
+        d.draw_line_v(
+            Vector2::from(camera.world_to_screen(positions[body_a])),
+            Vector2::from(camera.world_to_screen(positions[body_b])),
+            Color::GREEN,
+        );
+    }
+}
+
+/// Overlays the solver diagnostics for the last step at `(x, y)`.
+pub fn draw_step_report(d: &mut RaylibDrawHandle, report: &StepReport, x: i32, y: i32) {
+    let timings = report.timings;
+    let lines = [
+        format!(
+            "{} rows ({} warm), {} iterations{}",
+            report.rows,
+            report.warm_rows,
+            report.iterations,
+            if report.converged {
+                ""
+            } else {
+                ", not converged"
+            }
+        ),
+        format!(
+            "Residual {:.3e}, max |lambda| {:.3e}",
+            report.residual, report.max_lambda
+        ),
+        format!(
+            "|C| max {:.3} mean {:.3}",
+            report.max_constraint_error, report.mean_constraint_error
+        ),
+        format!(
+            "Forces {:.2} ms, assembly {:.2} ms, solve {:.2} ms, integrate {:.2} ms",
+            timings.forces.as_secs_f64() * 1000_f64,
+            timings.assembly.as_secs_f64() * 1000_f64,
+            timings.solve.as_secs_f64() * 1000_f64,
+            timings.integration.as_secs_f64() * 1000_f64,
+        ),
+    ];
+    for (line_index, line) in lines.iter().enumerate() {
+        d.draw_text(line, x, y + line_index as i32 * 16, 14, Color::DARKGRAY);
+    }
+}